@@ -4,6 +4,8 @@ use clap::{App, AppSettings};
 use minitt_util::cli::{cli_completion_generation, GenShellSubCommand};
 use structopt::StructOpt;
 
+use crate::config::LoadOptions;
+
 #[derive(StructOpt)]
 #[structopt(
     about,
@@ -39,6 +41,22 @@ pub struct CliOptions {
     #[structopt(alias = "dr", long)]
     pub debug_response: bool,
 
+    /// Run the commands in this file (`-` for stdin) instead of the REPL.
+    /// Exit code: 1 on Agda errors, 2 on script errors (like a bad goal id), 3 if goals are left
+    #[structopt(long, name = "script")]
+    pub script: Option<PathBuf>,
+
+    /// `text`, or `json` for one JSON object per command on stdout
+    #[structopt(long, name = "format", default_value = "text", possible_values = &["text", "json"])]
+    pub output: String,
+
+    /// With `--script`, write the final proof to this file
+    #[structopt(long, name = "proof")]
+    pub proof: Option<PathBuf>,
+
+    #[structopt(flatten)]
+    pub load: LoadOptions,
+
     #[structopt(subcommand)]
     completion: Option<GenShellSubCommand>,
 }
@@ -0,0 +1,148 @@
+use std::collections::{BTreeMap, BTreeSet};
+use std::io;
+use std::sync::{Arc, Mutex};
+
+use agda_mode::cmd::{Cmd, GoalInput};
+use agda_mode::pos::InteractionId;
+
+use crate::file_io::Repl;
+use crate::highlight::{token_kinds, TokenKind};
+use crate::input::UserInput;
+
+/// Things to complete or hint, refreshed after each load.
+#[derive(Debug, Default)]
+pub struct CompletionCache {
+    /// The [`load_count`](ReplState::load_count) of the cached contexts and names.
+    load: Option<usize>,
+    pub goals: Vec<InteractionId>,
+    /// The goal types from the latest `AllGoalsWarnings`.
+    pub types: BTreeMap<InteractionId, String>,
+    /// Variables in scope in the context of each goal.
+    pub contexts: BTreeMap<InteractionId, Vec<String>>,
+    /// Top-level names, from the module contents.
+    pub names: BTreeSet<String>,
+    /// Names in the file, from the highlighting.
+    pub kinds: BTreeMap<String, TokenKind>,
+}
+
+/// Shared between the REPL and the (synchronous) editor.
+pub type SharedCache = Arc<Mutex<CompletionCache>>;
+
+/// Update the goals, and after a load, ask Agda for the contexts and the top-level names.
+pub async fn refresh(repl: &mut Repl, cache: &SharedCache) -> io::Result<()> {
+    let kinds = token_kinds(&repl.source(), repl.agda.highlighting());
+    let agda = &mut repl.agda;
+    let goals: Vec<InteractionId> = agda.interaction_points().iter().map(|ip| ip.id).collect();
+    let types = agda
+        .goals()
+        .into_iter()
+        .filter_map(|goal| Some((goal.id(), goal.r#type?)))
+        .collect();
+    let load = agda.load_count();
+    if cache.lock().unwrap().load != Some(load) {
+        // These queries are ours, the user may still jump to the error.
+        let saved = agda.save_error();
+        let mut contexts = BTreeMap::new();
+        for &i in &goals {
+            agda.command(Cmd::context(GoalInput::simple(i))).await?;
+            if let Ok(ctx) = agda.next_context().await? {
+                let names = ctx
+                    .context
+                    .into_iter()
+                    .filter(|e| e.in_scope)
+                    .map(|e| e.reified_name)
+                    .collect();
+                contexts.insert(i, names);
+            }
+        }
+        agda.command(Cmd::search_module(String::new())).await?;
+        let names = match agda.next_module_contents().await? {
+            Ok(contents) => {
+                let names = contents.contents.into_iter().map(|c| c.name);
+                names.chain(contents.names).collect()
+            }
+            Err(_) => BTreeSet::new(),
+        };
+        agda.restore_error(saved);
+        let mut cache = cache.lock().unwrap();
+        cache.load = Some(load);
+        cache.contexts = contexts;
+        cache.names = names;
+        cache.kinds = kinds;
+    }
+    let mut cache = cache.lock().unwrap();
+    cache.goals = goals;
+    cache.types = types;
+    Ok(())
+}
+
+fn is_word_boundary(c: char) -> bool {
+    c.is_whitespace() || "(){};".contains(c)
+}
+
+impl CompletionCache {
+    /// Completions for `line` (up to the cursor), and where the completed word starts.
+    pub fn complete(&self, line: &str) -> (usize, Vec<String>) {
+        let start = line.len() - line.trim_start().len();
+        let line = &line[start..];
+        let command_end = match line.find(char::is_whitespace) {
+            None => {
+                let commands = UserInput::values().iter().filter(|s| s.starts_with(line));
+                return (start, commands.map(|&s| s.to_owned()).collect());
+            }
+            Some(idx) => idx,
+        };
+        let command = &line[..command_end];
+        if !UserInput::takes_goal(command) {
+            return (start + line.len(), vec![]);
+        }
+        let args = &line[command_end..];
+        let goal_start = command_end + args.len() - args.trim_start().len();
+        let args = args.trim_start();
+        let goal = match args.find(char::is_whitespace) {
+            None => {
+                let goals = self.goals.iter().map(|i| i.to_string());
+                let goals = goals.filter(|i| i.starts_with(args)).collect();
+                return (start + goal_start, goals);
+            }
+            Some(idx) => &args[..idx],
+        };
+        if !UserInput::takes_code(command) {
+            return (start + line.len(), vec![]);
+        }
+        let word_start = line
+            .rfind(is_word_boundary)
+            .map_or(0, |i| i + line[i..].chars().next().unwrap().len_utf8());
+        let word = &line[word_start..];
+        let context = goal.parse().ok().and_then(|i| self.contexts.get(&i));
+        let mut candidates: Vec<String> = context
+            .into_iter()
+            .flatten()
+            .chain(&self.names)
+            .filter(|name| name.starts_with(word))
+            .cloned()
+            .collect();
+        candidates.sort();
+        candidates.dedup();
+        (start + word_start, candidates)
+    }
+
+    /// After `give 3 ` (or other commands taking code), the type of the goal,
+    /// and after `split 2 `, also the variables to split on.
+    pub fn hint(&self, line: &str) -> Option<String> {
+        let mut words = line.split_whitespace();
+        let command = words.next()?;
+        let goal: InteractionId = words.next()?.parse().ok()?;
+        if words.next().is_some() || !line.ends_with(' ') || !UserInput::takes_code(command) {
+            return None;
+        }
+        let ty = self.types.get(&goal)?;
+        let vars = match self.contexts.get(&goal) {
+            Some(vars) if command == "split" && !vars.is_empty() => {
+                format!("{} ", vars.join(" | "))
+            }
+            _ => String::new(),
+        };
+        Some(format!("{}-- ?{} : {}", vars, goal, ty))
+    }
+}
@@ -0,0 +1,314 @@
+use std::collections::BTreeMap;
+use std::fs::read_to_string;
+use std::path::{Path, PathBuf};
+
+use serde::Deserialize;
+use structopt::StructOpt;
+use toml::Value;
+
+use agda_mode::base::{ComputeMode, Rewrite};
+use agda_mode::cmd::AgdaOptions;
+
+use crate::file_io::{config_dir, Monad};
+use crate::highlight::Theme;
+use crate::input::UserInput;
+
+/// Per-project configuration, in the directory of the file or above.
+pub const PROJECT_CONFIG: &str = ".agda-tac.toml";
+
+/// Options for loading the file, from the command line or the `[load]` table.
+#[derive(StructOpt, Deserialize, Debug, Clone, Default)]
+#[serde(default, rename_all = "kebab-case", deny_unknown_fields)]
+pub struct LoadOptions {
+    /// Look for imports in this directory (can be repeated)
+    #[structopt(short = "i", long, name = "dir", number_of_values = 1)]
+    pub include_path: Vec<PathBuf>,
+
+    /// Use this Agda library (can be repeated)
+    #[structopt(short = "l", long, name = "lib", number_of_values = 1)]
+    pub library: Vec<String>,
+
+    /// Pass `--safe` to Agda
+    #[structopt(long)]
+    pub safe: bool,
+
+    /// Pass `--without-K` to Agda
+    #[structopt(long = "without-K")]
+    #[serde(rename = "without-K")]
+    pub without_k: bool,
+
+    /// Pass `--cubical` to Agda
+    #[structopt(long)]
+    pub cubical: bool,
+
+    /// Pass `--guardedness` to Agda
+    #[structopt(long)]
+    pub guardedness: bool,
+
+    /// Pass `--sized-types` to Agda
+    #[structopt(long)]
+    pub sized_types: bool,
+
+    /// Agda's verbosity, like `tc.meta:20` (can be repeated)
+    #[structopt(short = "v", long, name = "key:level", number_of_values = 1)]
+    pub verbose: Vec<String>,
+}
+
+/// Command aliases, like `g = "give"`. They can't shadow the commands.
+#[derive(Deserialize, Debug, Clone, Default)]
+#[serde(transparent)]
+pub struct Aliases(BTreeMap<String, String>);
+
+impl Aliases {
+    pub fn get(&self, word: &str) -> Option<&str> {
+        if UserInput::values().contains(&word) {
+            None
+        } else {
+            self.0.get(word).map(|s| s.as_str())
+        }
+    }
+
+    pub fn names(&self) -> impl Iterator<Item = &str> {
+        self.0.keys().map(|s| s.as_str())
+    }
+
+    /// Replace the alias at the beginning of `line`.
+    pub fn expand(&self, line: &str) -> String {
+        let (word, rest) = line.split_once(' ').unwrap_or((line, ""));
+        match self.get(word) {
+            Some(expansion) if rest.is_empty() => expansion.to_owned(),
+            Some(expansion) => format!("{} {}", expansion, rest),
+            None => line.to_owned(),
+        }
+    }
+}
+
+/// `~/.agda-tac/config.toml`, overridden by the nearest [`PROJECT_CONFIG`].
+#[derive(Deserialize, Debug, Clone, Default)]
+#[serde(default, rename_all = "kebab-case", deny_unknown_fields)]
+pub struct Config {
+    /// The Agda executable, `agda` by default.
+    pub agda_path: Option<PathBuf>,
+    /// Arguments for the Agda process, like `["+RTS", "-M4G", "-RTS"]`.
+    pub agda_args: Vec<String>,
+    /// Normalization of goals, contexts and inferred types, like `Normalised`.
+    pub rewrite: Rewrite,
+    /// Computation mode of `simpl`, like `IgnoreAbstract`.
+    pub compute: ComputeMode,
+    pub plain: bool,
+    pub history_size: Option<usize>,
+    pub theme: Theme,
+    pub aliases: Aliases,
+    pub load: LoadOptions,
+}
+
+impl LoadOptions {
+    /// The command line adds to the configuration,
+    /// the paths and libraries in both are kept once.
+    pub fn merge(mut self, other: LoadOptions) -> Self {
+        for path in other.include_path {
+            if !self.include_path.contains(&path) {
+                self.include_path.push(path);
+            }
+        }
+        for library in other.library {
+            if !self.library.contains(&library) {
+                self.library.push(library);
+            }
+        }
+        self.safe |= other.safe;
+        self.without_k |= other.without_k;
+        self.cubical |= other.cubical;
+        self.guardedness |= other.guardedness;
+        self.sized_types |= other.sized_types;
+        self.verbose.extend(other.verbose);
+        self
+    }
+
+    /// The flags for loading the file.
+    pub fn flags(&self) -> Result<Vec<String>, String> {
+        let mut options = AgdaOptions::new()
+            .safe(self.safe)
+            .without_k(self.without_k)
+            .cubical(self.cubical)
+            .guardedness(self.guardedness)
+            .sized_types(self.sized_types);
+        for path in &self.include_path {
+            options = options.include_path(path.clone());
+        }
+        for library in &self.library {
+            options = options.library(library.clone());
+        }
+        for verbose in &self.verbose {
+            let (key, level) = verbose.rsplit_once(':').unwrap_or(("", verbose));
+            let level = level
+                .parse()
+                .map_err(|_| format!("Invalid verbosity `{}`, expected `key:level`.", verbose))?;
+            options = options.verbose(key, level);
+        }
+        options.flags()
+    }
+}
+
+/// Later tables override the earlier ones key by key.
+fn merge(into: &mut Value, from: Value) {
+    match (into, from) {
+        (Value::Table(into), Value::Table(from)) => {
+            for (key, value) in from {
+                match into.get_mut(&key) {
+                    Some(old) => merge(old, value),
+                    None => {
+                        into.insert(key, value);
+                    }
+                }
+            }
+        }
+        (into, from) => *into = from,
+    }
+}
+
+/// Make the relative paths in a configuration relative to its directory.
+fn resolve_paths(config: &mut Value, dir: &Path) {
+    let resolve = |path: &mut Value| {
+        if let Value::String(s) = path {
+            let relative = Path::new(s.as_str());
+            // `agda` alone is looked up in `PATH`.
+            if relative.is_relative() && relative.components().count() > 1 {
+                *s = dir.join(relative).display().to_string();
+            }
+        }
+    };
+    if let Some(path) = config.get_mut("agda-path") {
+        resolve(path);
+    }
+    let include = config
+        .get_mut("load")
+        .and_then(|l| l.get_mut("include-path"));
+    if let Some(Value::Array(paths)) = include {
+        for path in paths {
+            if let Value::String(s) = path {
+                if Path::new(s.as_str()).is_relative() {
+                    *s = dir.join(s.as_str()).display().to_string();
+                }
+            }
+        }
+    }
+}
+
+impl Config {
+    /// Read the global configuration and the project configuration of `file`,
+    /// the default if there's none.
+    pub fn load(file: &Path) -> Monad<Result<Self, String>> {
+        let global = config_dir()?.join("config.toml");
+        let project = file
+            .ancestors()
+            .skip(1)
+            .map(|dir| dir.join(PROJECT_CONFIG))
+            .find(|path| path.is_file());
+        Self::load_files(std::iter::once(global).chain(project))
+    }
+
+    /// The later files override the earlier ones, missing files are skipped.
+    fn load_files(paths: impl Iterator<Item = PathBuf>) -> Monad<Result<Self, String>> {
+        let mut config = Value::Table(Default::default());
+        for path in paths {
+            if !path.is_file() {
+                continue;
+            }
+            let error = |e: toml::de::Error| format!("{}: {}", path.display(), e);
+            let mut value: Value = match read_to_string(&path)?.parse() {
+                Ok(value) => value,
+                Err(e) => return Ok(Err(error(e))),
+            };
+            // Report the errors in the file they come from.
+            if let Err(e) = value.clone().try_into::<Config>() {
+                return Ok(Err(error(e)));
+            }
+            resolve_paths(&mut value, path.parent().unwrap_or_else(|| Path::new(".")));
+            merge(&mut config, value);
+        }
+        Ok(config.try_into().map_err(|e| e.to_string()))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::fs::{create_dir_all, write};
+
+    use super::*;
+
+    /// A fresh directory for the test's files.
+    fn temp_dir(name: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("agda-tac-{}-{}", name, std::process::id()));
+        create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    #[test]
+    fn merge_tables() {
+        let mut config: Value = "plain = true\n[load]\nsafe = true\nlibrary = [\"std\"]"
+            .parse()
+            .unwrap();
+        let project: Value = "[load]\nlibrary = [\"cubical\"]".parse().unwrap();
+        merge(&mut config, project);
+        let config: Config = config.try_into().unwrap();
+        assert!(config.plain);
+        assert!(config.load.safe);
+        assert_eq!(config.load.library, vec!["cubical"]);
+        let command_line = LoadOptions {
+            library: vec!["cubical".to_owned(), "std".to_owned()],
+            ..Default::default()
+        };
+        let load = config.load.merge(command_line);
+        assert_eq!(load.library, vec!["cubical", "std"]);
+        assert!(load.flags().is_ok());
+    }
+
+    #[test]
+    fn paths() {
+        let mut config: Value =
+            "agda-path = \"bin/agda\"\n[load]\ninclude-path = [\"src\", \"/abs\"]"
+                .parse()
+                .unwrap();
+        resolve_paths(&mut config, Path::new("/project"));
+        let config: Config = config.try_into().unwrap();
+        assert_eq!(config.agda_path, Some(PathBuf::from("/project/bin/agda")));
+        let include = vec![PathBuf::from("/project/src"), PathBuf::from("/abs")];
+        assert_eq!(config.load.include_path, include);
+        // Looked up in `PATH`.
+        let mut config: Value = "agda-path = \"agda\"".parse().unwrap();
+        resolve_paths(&mut config, Path::new("/project"));
+        assert_eq!(config["agda-path"].as_str(), Some("agda"));
+    }
+
+    #[test]
+    fn files() {
+        let dir = temp_dir("config");
+        let global = dir.join("config.toml");
+        let project = dir.join("project").join(PROJECT_CONFIG);
+        create_dir_all(project.parent().unwrap()).unwrap();
+        write(&global, "history-size = 10\nrewrite = \"Normalised\"\n").unwrap();
+        write(
+            &project,
+            "history-size = 20\n[load]\ninclude-path = [\"src\"]\n",
+        )
+        .unwrap();
+        let paths = vec![global.clone(), project.clone(), dir.join("missing.toml")];
+        let config = Config::load_files(paths.into_iter()).unwrap().unwrap();
+        assert_eq!(config.history_size, Some(20));
+        assert_eq!(config.rewrite, Rewrite::Normalised);
+        let src = dir.join("project").join("src");
+        assert_eq!(config.load.include_path, vec![src]);
+        // Unknown keys are reported with the file they are in.
+        write(&project, "histroy-size = 20\n").unwrap();
+        let paths = vec![global, project.clone()];
+        let error = Config::load_files(paths.into_iter()).unwrap().unwrap_err();
+        assert!(
+            error.starts_with(&project.display().to_string()),
+            "{}",
+            error
+        );
+        assert!(error.contains("histroy-size"), "{}", error);
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+}
@@ -1,12 +1,18 @@
-use rustyline::{completion::Completer, validate::Validator};
+use std::borrow::Cow::{self, Owned};
+
 use rustyline::highlight::Highlighter;
 use rustyline::hint::Hinter;
+use rustyline::{completion::Completer, validate::Validator};
 use rustyline::{CompletionType, Config, Context, Editor, Helper};
 
-use crate::input::UserInput;
+use crate::completion::SharedCache;
+use crate::config::Aliases;
+use crate::highlight::{highlight_line, Theme};
 
 pub struct CliEditor {
-    // TODO
+    pub cache: SharedCache,
+    pub theme: Theme,
+    pub aliases: Aliases,
 }
 
 impl Completer for CliEditor {
@@ -18,44 +24,56 @@ impl Completer for CliEditor {
         pos: usize,
         _: &Context<'_>,
     ) -> rustyline::Result<(usize, Vec<Self::Candidate>)> {
-        let start = line
-            .chars()
-            .enumerate()
-            .find(|(_, i)| !i.is_whitespace())
-            .map(|(i, _)| i)
-            .unwrap_or(0);
-        let subs = if pos > start {
-            &line[start..pos]
-        } else {
-            &line[start..]
-        };
-        let base = UserInput::values()
-            .iter()
-            .filter(|s| s.starts_with(subs))
-            .map(|&s| s.to_owned())
-            .collect();
-        Ok((start, base))
+        let line = &line[..pos];
+        let (start, mut candidates) = self.cache.lock().unwrap().complete(line);
+        if !line.trim_start().contains(char::is_whitespace) {
+            let aliases = self
+                .aliases
+                .names()
+                .filter(|a| a.starts_with(line.trim_start()));
+            candidates.extend(aliases.map(str::to_owned));
+        }
+        Ok((start, candidates))
     }
 }
 
 impl Hinter for CliEditor {
-    // TODO
+    fn hint(&self, line: &str, pos: usize, _: &Context<'_>) -> Option<String> {
+        if pos < line.len() {
+            return None;
+        }
+        self.cache.lock().unwrap().hint(&self.aliases.expand(line))
+    }
 }
 
-impl Highlighter for CliEditor {}
+impl Highlighter for CliEditor {
+    fn highlight<'l>(&self, line: &'l str, _: usize) -> Cow<'l, str> {
+        let cache = self.cache.lock().unwrap();
+        Owned(highlight_line(&cache, &self.theme, &self.aliases, line))
+    }
+
+    fn highlight_char(&self, _: &str, _: usize) -> bool {
+        true
+    }
+
+    fn highlight_hint<'h>(&self, hint: &'h str) -> Cow<'h, str> {
+        Owned(Theme::paint(&self.theme.hint, hint))
+    }
+}
 
 impl Validator for CliEditor {}
 
 impl Helper for CliEditor {}
 
 impl CliEditor {
-    pub fn into_editor(self) -> Editor<CliEditor> {
-        let mut r = Editor::with_config(
-            Config::builder()
-                .history_ignore_space(true)
-                .completion_type(CompletionType::List)
-                .build(),
-        );
+    pub fn into_editor(self, history_size: Option<usize>) -> Editor<CliEditor> {
+        let mut config = Config::builder()
+            .history_ignore_space(true)
+            .completion_type(CompletionType::List);
+        if let Some(size) = history_size {
+            config = config.max_history_size(size);
+        }
+        let mut r = Editor::with_config(config.build());
         r.set_helper(Some(self));
         r
     }
@@ -1,12 +1,18 @@
 use std::fs::{create_dir_all, remove_file, File, OpenOptions};
-use std::io::{self, BufReader, BufWriter, Seek, SeekFrom, Write};
-use std::ops::Range;
+use std::io::{self, BufReader, BufWriter, ErrorKind, Seek, SeekFrom, Write};
 use std::path::PathBuf;
 
 use ropey::{Rope, RopeSlice};
 
 use agda_mode::agda::ReplState;
-use agda_mode::pos::{InteractionPoint, Interval};
+use agda_mode::edit::TextEdit;
+use agda_mode::library::check_module_name;
+use agda_mode::pos::InteractionPoint;
+
+use crate::config::Config;
+use crate::literate::Literate;
+use crate::output::{report, Report};
+use crate::project::Project;
 
 const FAIL_CREATE_DEFAULT: &str = "Failed to create default working file";
 
@@ -15,31 +21,44 @@ pub type Monad<T = ()> = io::Result<T>;
 #[derive(Debug)]
 pub struct InitModule(pub File, pub PathBuf, pub Rope);
 
-pub fn init_module(mut file: PathBuf, allow_ex: bool) -> Monad<InitModule> {
-    file.set_extension("agda");
+/// Open `file`, or create it with a module header, named after its path in the `project`.
+pub fn init_module(mut file: PathBuf, project: &Project, allow_ex: bool) -> Monad<InitModule> {
+    let file_name = file.file_name().and_then(|name| name.to_str());
+    let literate = match file_name.and_then(Literate::of_file_name) {
+        Some((literate, _)) => literate,
+        None => {
+            file.set_extension("agda");
+            Literate::Plain
+        }
+    };
     let path = &file;
     if path.exists() {
         if !allow_ex {
-            eprintln!("I don't want to work with existing files, sorry.");
-            std::process::exit(1);
+            let e = "I don't want to work with existing files, sorry.";
+            return Err(io::Error::new(ErrorKind::AlreadyExists, e));
         } else {
             let file = OpenOptions::new().read(true).write(true).open(path)?;
             let mut perms = file.metadata()?.permissions();
+            #[allow(clippy::permissions_set_readonly_false)]
             perms.set_readonly(false);
             file.set_permissions(perms)?;
             let rope = Rope::from_reader(BufReader::new(&file))?;
             return Ok(InitModule(file, path.canonicalize()?, rope));
         }
     }
-    let mod_name = path
-        .file_stem()
-        .expect(&format!("File does not have a name: {:?}", path));
-    // TODO: check if it's a valid module name
-    let first_line = format!("module {} where\n", mod_name.display());
+    let invalid = |e: String| io::Error::new(ErrorKind::InvalidInput, e);
+    let mod_name = project
+        .module_name(path)
+        .ok_or_else(|| invalid(format!("File does not have a name: {:?}", path)))?;
+    check_module_name(&mod_name).map_err(invalid)?;
+    if let Some(dir) = path.parent() {
+        create_dir_all(dir)?;
+    }
+    let content = literate.template(&mod_name);
     let mut f = File::create(path)?;
-    f.write(first_line.as_bytes())?;
+    f.write_all(content.as_bytes())?;
     f.flush()?;
-    Ok(InitModule(f, path.canonicalize()?, Rope::from(first_line)))
+    Ok(InitModule(f, path.canonicalize()?, Rope::from(content)))
 }
 
 pub fn find_default_unwrap() -> PathBuf {
@@ -59,19 +78,15 @@ pub fn history_file() -> Monad<PathBuf> {
 }
 
 pub fn find_default() -> Monad<PathBuf> {
-    println!("No input file specified, using default.");
+    eprintln!("No input file specified, using default.");
     let file_path = config_dir()?.join("Nameless.agda");
-    println!("Default to {}", file_path.display());
+    eprintln!("Default to {}", file_path.display());
     if file_path.exists() {
         remove_file(&file_path)?;
     }
     Ok(file_path)
 }
 
-pub fn agda_to_rope_range(i: &Interval) -> Range<usize> {
-    i.range_shift_left(1)
-}
-
 #[derive(Debug)]
 pub struct Repl {
     pub agda: ReplState,
@@ -79,17 +94,24 @@ pub struct Repl {
     #[allow(dead_code)]
     pub path: PathBuf,
     file_buf: Rope,
+    literate: Literate,
     pub is_plain: bool,
+    pub config: Config,
+    pub project: Project,
 }
 
 impl Repl {
     pub fn new(agda: ReplState, file: File, path: PathBuf, file_buf: Rope) -> Self {
+        let literate = Literate::of_path(&path);
         Self {
             agda,
             file,
             path,
             file_buf,
+            literate,
             is_plain: false,
+            config: Default::default(),
+            project: Default::default(),
         }
     }
 
@@ -100,7 +122,7 @@ impl Repl {
 
     pub fn remove_last_line_buffer(&mut self) {
         if self.file_buf.len_lines() < 2 {
-            eprintln!("Error: line buffer is empty");
+            report(Report::error("The line buffer is empty.".to_owned()));
             return;
         }
         let line_last = self.file_buf.len_lines() - 2;
@@ -109,21 +131,21 @@ impl Repl {
         self.file_buf.remove(line_start..doc_end)
     }
 
-    pub fn remove_line_buffer(&mut self, line_num: usize) {
-        // Previous line
-        let line_start = self.file_buf.line_to_char(line_num - 1);
-        let line_end = self.file_buf.line_to_char(line_num);
-        self.file_buf.remove(line_start..line_end)
+    /// The whole buffer, for computing [`TextEdit`]s.
+    pub fn source(&self) -> String {
+        self.file_buf.to_string()
     }
 
-    pub fn line_of_offset(&mut self, offset: usize) -> usize {
-        self.file_buf.char_to_line(offset)
-    }
-
-    pub fn fill_goal_buffer(&mut self, i: InteractionPoint, text: &str) {
-        let interval = i.the_interval();
-        self.file_buf.remove(agda_to_rope_range(interval));
-        self.file_buf.insert(interval.start.pos - 1, text);
+    /// Apply the edits to the buffer, and move the goals accordingly.
+    pub fn apply_edits(&mut self, edits: &[TextEdit]) {
+        let mut edits_rev: Vec<&TextEdit> = edits.iter().collect();
+        edits_rev.sort_by_key(|e| std::cmp::Reverse(e.range.start));
+        for edit in edits_rev {
+            self.file_buf.remove(edit.range.clone());
+            self.file_buf.insert(edit.range.start, &edit.text);
+        }
+        let source = self.source();
+        self.agda.shift_goals(edits, &source);
     }
 
     pub fn intros_in_goal_buffer(&mut self, i: InteractionPoint, text: &str) -> Option<()> {
@@ -137,18 +159,12 @@ impl Repl {
         Some(())
     }
 
-    pub fn insert_line_buffer(&mut self, line_num: usize, line: &str) {
-        // Previous line
-        let index = self.file_buf.line_to_char(line_num - 1) - 1;
-        self.file_buf.insert(index, line);
-        self.file_buf.insert(index, "\n")
-    }
-
-    pub fn dump_proof(&mut self) -> Monad {
-        self.file_buf.write_to(BufWriter::new(io::stdout()))
+    pub fn dump_proof(&mut self) {
+        let text = self.source();
+        report(Report::Proof { text })
     }
 
-    pub fn line_in_buffer(&mut self, line_num: usize) -> RopeSlice {
+    pub fn line_in_buffer(&mut self, line_num: usize) -> RopeSlice<'_> {
         self.file_buf.line(line_num)
     }
 
@@ -161,16 +177,71 @@ impl Repl {
     }
 
     pub fn append(&mut self, text: &str) -> Monad {
+        self.agda.mark_buffer_changed();
         self.append_buffer(text);
         Self::append_to_file(&mut self.file, text.as_bytes())?;
         self.flush_file()
     }
 
-    pub fn remove_last_line(&mut self) -> Monad {
-        self.remove_last_line_buffer();
+    /// Add lines of code at the end of the last code block
+    /// (or in a new code block if there's none).
+    /// `code` shouldn't end with a newline.
+    pub fn push_code(&mut self, code: &str) -> Monad {
+        if self.literate == Literate::Plain {
+            return self.append(&format!("{}\n", code));
+        }
+        self.agda.mark_buffer_changed();
+        let source = self.source();
+        let edit = match self.literate.code_blocks(&source).last() {
+            Some(block) => {
+                let indent = self.literate.code_indent();
+                let mut text = String::new();
+                let at = self.line_start(block.end);
+                if at == self.file_buf.len_chars() && !source.ends_with('\n') {
+                    text.push('\n');
+                }
+                for line in code.lines() {
+                    text.push_str(indent);
+                    text.push_str(line);
+                    text.push('\n');
+                }
+                TextEdit::insert(at, text)
+            }
+            None => TextEdit::insert(self.file_buf.len_chars(), self.literate.wrap(code)),
+        };
+        self.apply_edits(&[edit]);
         self.sync_buffer()
     }
 
+    /// Remove the last line of code.
+    pub fn remove_last_line(&mut self) -> Monad {
+        self.agda.mark_buffer_changed();
+        if self.literate == Literate::Plain {
+            self.remove_last_line_buffer();
+            return self.sync_buffer();
+        }
+        let source = self.source();
+        match self.literate.code_blocks(&source).last() {
+            Some(block) if !block.is_empty() => {
+                let range = self.line_start(block.end - 1)..self.line_start(block.end);
+                self.apply_edits(&[TextEdit::new(range, String::new())]);
+                self.sync_buffer()
+            }
+            _ => {
+                report(Report::error("The line buffer is empty.".to_owned()));
+                Ok(())
+            }
+        }
+    }
+
+    fn line_start(&self, line: usize) -> usize {
+        if line >= self.file_buf.len_lines() {
+            self.file_buf.len_chars()
+        } else {
+            self.file_buf.line_to_char(line)
+        }
+    }
+
     fn append_to_file(file: &mut File, text: &[u8]) -> Monad<usize> {
         file.write(text)
     }
@@ -0,0 +1,258 @@
+use std::collections::BTreeMap;
+use std::iter::Peekable;
+
+use agda_mode::resp::AspectHighlight;
+use serde::Deserialize;
+
+use crate::completion::CompletionCache;
+use crate::config::Aliases;
+use crate::input::UserInput;
+
+/// How a token in an Agda expression is coloured.
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+pub enum TokenKind {
+    Keyword,
+    Symbol,
+    Bound,
+    Constructor,
+    Defined,
+}
+
+const KEYWORDS: &[&str] = &[
+    "let",
+    "in",
+    "where",
+    "with",
+    "rewrite",
+    "λ",
+    "\\",
+    "forall",
+    "∀",
+    "do",
+    "open",
+    "import",
+    "module",
+    "data",
+    "record",
+    "field",
+    "constructor",
+    "postulate",
+    "mutual",
+    "abstract",
+    "private",
+    "instance",
+    "using",
+    "hiding",
+    "renaming",
+    "to",
+    "public",
+];
+
+const SYMBOLS: &[&str] = &[
+    "→", "->", ":", "=", "|", ".", "@", "_", "?", "(", ")", "{", "}", ";",
+];
+
+impl TokenKind {
+    /// Classify Agda's highlighting aspects.
+    pub fn of_atoms(atoms: &[String]) -> Option<Self> {
+        atoms.iter().find_map(|atom| match atom.as_str() {
+            "keyword" => Some(TokenKind::Keyword),
+            "symbol" => Some(TokenKind::Symbol),
+            "bound" | "generalizable" => Some(TokenKind::Bound),
+            "inductiveconstructor" | "coinductiveconstructor" => Some(TokenKind::Constructor),
+            "datatype" | "function" | "field" | "postulate" | "primitive" | "record" | "macro"
+            | "module" => Some(TokenKind::Defined),
+            _ => None,
+        })
+    }
+}
+
+/// The names in `source`, classified by its highlighting.
+pub fn token_kinds(source: &str, highlighting: &[AspectHighlight]) -> BTreeMap<String, TokenKind> {
+    let chars: Vec<char> = source.chars().collect();
+    let mut kinds = BTreeMap::new();
+    for aspect in highlighting {
+        let kind = match TokenKind::of_atoms(&aspect.atoms) {
+            Some(kind) => kind,
+            None => continue,
+        };
+        // Agda's positions are 1-indexed.
+        let (from, to) = aspect.range;
+        let (from, to) = ((from - 1).max(0) as usize, (to - 1).max(0) as usize);
+        if from < to && to <= chars.len() {
+            let name: String = chars[from..to].iter().collect();
+            kinds.insert(name, kind);
+        }
+    }
+    kinds
+}
+
+/// Colours of the input line, as ANSI codes like `1;34` or names like `bold blue`.
+#[derive(Deserialize, Debug, Clone)]
+#[serde(default, rename_all = "kebab-case", deny_unknown_fields)]
+pub struct Theme {
+    pub command: String,
+    pub unknown_command: String,
+    pub goal: String,
+    pub unknown_goal: String,
+    pub keyword: String,
+    pub symbol: String,
+    pub bound: String,
+    pub constructor: String,
+    pub defined: String,
+    pub hint: String,
+}
+
+impl Default for Theme {
+    fn default() -> Self {
+        let color = |c: &str| c.to_owned();
+        Theme {
+            command: color("1"),
+            unknown_command: color("31"),
+            goal: color("32"),
+            unknown_goal: color("4;31"),
+            keyword: color("33"),
+            symbol: color("90"),
+            bound: color("35"),
+            constructor: color("32"),
+            defined: color("34"),
+            hint: color("2"),
+        }
+    }
+}
+
+const COLOR_NAMES: &[(&str, &str)] = &[
+    ("bold", "1"),
+    ("dim", "2"),
+    ("italic", "3"),
+    ("underline", "4"),
+    ("black", "30"),
+    ("red", "31"),
+    ("green", "32"),
+    ("yellow", "33"),
+    ("blue", "34"),
+    ("magenta", "35"),
+    ("cyan", "36"),
+    ("white", "37"),
+    ("gray", "90"),
+];
+
+impl Theme {
+    fn token(&self, kind: TokenKind) -> &str {
+        match kind {
+            TokenKind::Keyword => &self.keyword,
+            TokenKind::Symbol => &self.symbol,
+            TokenKind::Bound => &self.bound,
+            TokenKind::Constructor => &self.constructor,
+            TokenKind::Defined => &self.defined,
+        }
+    }
+
+    /// `text` in the colour `color`.
+    pub fn paint(color: &str, text: &str) -> String {
+        let codes: Vec<&str> = color
+            .split(|c: char| c.is_whitespace() || c == ';' || c == '+')
+            .filter(|code| !code.is_empty())
+            .map(|code| {
+                let name = COLOR_NAMES.iter().find(|(name, _)| *name == code);
+                name.map_or(code, |(_, code)| code)
+            })
+            .collect();
+        format!("\x1b[{}m{}\x1b[0m", codes.join(";"), text)
+    }
+}
+
+fn paint(out: &mut String, color: &str, text: &str) {
+    out.push_str(&Theme::paint(color, text));
+}
+
+fn is_delimiter(c: char) -> bool {
+    "(){};".contains(c)
+}
+
+/// Split `line` into whitespace, delimiters and words.
+fn tokens(line: &str) -> Vec<&str> {
+    let mut tokens = Vec::new();
+    let mut start = 0;
+    let mut chars = line.char_indices().peekable();
+    while let Some((i, c)) = chars.next() {
+        let same_kind = |d: char| {
+            !is_delimiter(c) && !is_delimiter(d) && c.is_whitespace() == d.is_whitespace()
+        };
+        match chars.peek() {
+            Some(&(_, d)) if same_kind(d) => {}
+            _ => {
+                tokens.push(&line[start..i + c.len_utf8()]);
+                start = i + c.len_utf8();
+            }
+        }
+    }
+    tokens
+}
+
+fn push_spaces<'a>(out: &mut String, words: &mut Peekable<impl Iterator<Item = &'a str>>) {
+    while let Some(space) = words.next_if(|w| w.trim().is_empty()) {
+        out.push_str(space);
+    }
+}
+
+/// Colour the command, the goal (red if it doesn't exist) and the expression.
+pub fn highlight_line(
+    cache: &CompletionCache,
+    theme: &Theme,
+    aliases: &Aliases,
+    line: &str,
+) -> String {
+    let mut out = String::with_capacity(line.len() * 2);
+    let mut words = tokens(line).into_iter().peekable();
+    push_spaces(&mut out, &mut words);
+    let word = match words.next() {
+        Some(word) => word,
+        None => return out,
+    };
+    let complete = words.peek().is_some();
+    // Aliases with arguments are not highlighted further.
+    let command = match aliases.get(word) {
+        Some(expansion) if expansion.trim().contains(char::is_whitespace) => "",
+        Some(expansion) => expansion.trim(),
+        None => word,
+    };
+    if UserInput::values().contains(&command) || aliases.get(word).is_some() {
+        paint(&mut out, &theme.command, word);
+    } else if complete {
+        paint(&mut out, &theme.unknown_command, word);
+    } else {
+        out.push_str(word);
+    }
+    push_spaces(&mut out, &mut words);
+    let mut goal = None;
+    if UserInput::takes_goal(command) {
+        if let Some(word) = words.next() {
+            goal = word.parse().ok();
+            match goal {
+                Some(i) if cache.goals.contains(&i) => paint(&mut out, &theme.goal, word),
+                _ => paint(&mut out, &theme.unknown_goal, word),
+            }
+        }
+    } else if !["line-push", "line-insert", "line-replace"].contains(&command) {
+        out.extend(words);
+        return out;
+    }
+    let context = goal.and_then(|i| cache.contexts.get(&i));
+    for word in words {
+        let kind = if KEYWORDS.contains(&word) {
+            Some(TokenKind::Keyword)
+        } else if SYMBOLS.contains(&word) {
+            Some(TokenKind::Symbol)
+        } else if context.is_some_and(|vars| vars.iter().any(|v| v == word)) {
+            Some(TokenKind::Bound)
+        } else {
+            cache.kinds.get(word).copied()
+        };
+        match kind {
+            Some(kind) => paint(&mut out, theme.token(kind), word),
+            None => out.push_str(word),
+        }
+    }
+    out
+}
@@ -0,0 +1,54 @@
+use agda_mode::agda::AgdaResult;
+use agda_mode::diag::ErrorKind;
+
+use crate::output::{report, Report};
+
+/// A hint about what to do next, for the errors we know about.
+pub fn hint(kind: &ErrorKind) -> Option<String> {
+    use ErrorKind::*;
+    Some(match kind {
+        NotInScope { names } => format!(
+            "`{}` is not defined, did you forget an `open import`?",
+            names.join("`, `")
+        ),
+        UnequalTerms { left, right, .. } => {
+            format!(
+                "`{}` and `{}` should be the same, try `infer` on them.",
+                left, right
+            )
+        }
+        MissingClauses { function, cases } => {
+            let function = function.as_deref().unwrap_or("the function");
+            let cases = cases.len();
+            format!("{} has {} missing case(s), try `split`.", function, cases)
+        }
+        TerminationFailure { functions } => format!(
+            "make sure the recursive calls in `{}` are on structurally smaller arguments.",
+            functions.join("`, `")
+        ),
+        UnsolvedMetas { .. } => "use `list-goals` to see what is left.".to_owned(),
+        ParseError { near: Some(near) } => format!("check the syntax near `{}`.", near),
+        ModuleNotFound { module } => format!(
+            "`{}` is not in the include path, check your `.agda-lib` file.",
+            module
+        ),
+        AmbiguousName { name, candidates } => format!(
+            "qualify `{}`, it could be any of `{}`.",
+            name,
+            candidates.join("`, `")
+        ),
+        _ => return None,
+    })
+}
+
+pub fn print_hint(kind: &ErrorKind) {
+    if let Some(hint) = hint(kind) {
+        eprintln!("Hint: {}", hint);
+    }
+}
+
+/// Like [`preprint_agda_result`](agda_mode::agda::preprint_agda_result),
+/// with a hint after the error.
+pub fn preprint_with_hint<T>(t: AgdaResult<T>) -> Option<T> {
+    t.map_err(|e| report(Report::error(e))).ok()
+}
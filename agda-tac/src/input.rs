@@ -8,6 +8,11 @@ pub enum UserInput<'a> {
     PopLine,
     DumpProof,
     ShowLine(usize),
+    /// Lines are 1-indexed, ranges are inclusive.
+    ShowLines(Option<(usize, usize)>),
+    InsertLine(usize, &'a str),
+    ReplaceLine(usize, &'a str),
+    DeleteLines(usize, usize),
     Give(InteractionId, &'a str),
     Split(InteractionId, &'a str),
     Reload,
@@ -16,12 +21,26 @@ pub enum UserInput<'a> {
     Help,
     ListGoals,
     IntroPattern(InteractionId, &'a str),
-    SearchModule(&'a str),
+    SearchAbout(&'a str),
+    ModuleContents(Option<InteractionId>, &'a str),
+    WhyInScope(Option<InteractionId>, &'a str),
+    Constraints,
+    Metas,
+    Compile(&'a str),
+    ModuleGraph(Option<&'a str>, Option<&'a str>),
     Exit,
     Infer(InteractionId, &'a str),
     Simplify(InteractionId, &'a str),
     Normalize(InteractionId, &'a str),
     Type(InteractionId),
+    Refine(InteractionId, &'a str),
+    /// The goal, the name of the new function and its arguments.
+    Extract(InteractionId, &'a str, &'a str),
+    Intro(InteractionId),
+    Auto(InteractionId, &'a str),
+    AutoAll,
+    Solve(InteractionId),
+    SolveAll,
     Unknown(Option<&'a str>),
     ToggleDebugCommand,
     ToggleDebugResponse,
@@ -33,6 +52,10 @@ static VALUES: &[&str] = &[
     "line-push",
     "line-pop",
     "line-show",
+    "line-insert",
+    "line-replace",
+    "line-delete",
+    "show",
     "context",
     "fill",
     "dump-proof",
@@ -42,35 +65,91 @@ static VALUES: &[&str] = &[
     "intro-pattern",
     "read-to-end",
     "list-goals",
+    "search-about",
+    "module-contents",
     "find-in-module",
+    "module-graph",
+    "why-in-scope",
+    "constraints",
+    "metas",
+    "compile",
     "infer",
     "simpl",
     "norm",
     "deduce",
     "type",
+    "refine",
+    "intro",
+    "extract",
+    "auto",
+    "auto-all",
+    "solve",
+    "solve-all",
     "exit",
     "quit",
     "debug-response",
     "debug-command",
 ];
 
+/// Commands whose first argument is a goal.
+static GOAL_COMMANDS: &[&str] = &[
+    "give",
+    "fill",
+    "split",
+    "intro-pattern",
+    "infer",
+    "deduce",
+    "simpl",
+    "norm",
+    "type",
+    "context",
+    "refine",
+    "intro",
+    "extract",
+    "auto",
+    "solve",
+];
+
 pub static HELP: &[&str] = &[
     "help: print this message.",
     "define <name>: define a function, with the given `name`.",
     "line-push <line>: push a `line` to the agda file, with leading whitespaces preserved.",
     "line-pop: pop the last line of the agda file.",
     "line-show <line>: show the `line`-th line.",
+    "show [n[..m]]: show the file (or the lines `n` to `m`) with line numbers and goals.",
+    "line-insert <n> <line>: insert a `line` before the `n`-th line.",
+    "line-replace <n> <line>: replace the `n`-th line with `line`.",
+    "line-delete <n>[..<m>]: delete the `n`-th line (or the lines `n` to `m`, also `n-m`).",
     "list-goals: list the goals and their line number.",
     "reload: let agda reload the current file.",
     "dump-proof: print the agda file.",
     "intro-pattern <goal> <var>: introduce a pattern of name `var` in `goal`.",
-    "find-in-module: find a definition in the current module. (mysterious API)",
+    "search-about <names...>: find definitions whose types mention all the `names`.",
+    "module-contents [goal] <module>: list the definitions in `module`, \
+     in the scope of `goal` if given (alias: find-in-module).",
+    "module-graph [dot|json] [file]: show the slowest modules of the last load, \
+     or export the module dependency graph.",
+    "why-in-scope [goal] <name>: explain where `name` comes from, \
+     in the scope of `goal` if given.",
+    "constraints: list the unsolved constraints, with their lines.",
+    "metas: list the goals and unsolved metas, with their lines.",
+    "compile [backend] [flags]: compile the file with `backend` (GHC by default), \
+     like GHCNoMain, JS, LaTeX, QuickLaTeX or HTML.",
     "read-to-end: consume all available agda responses, for debugging agda-tac only.",
     "fill <goal> <code>: fill the `goal` with `code` (alias: give).",
     "infer <goal> <code>: infer the type of `code` under the context of `goal` (alias: deduce).",
     "norm <goal> <code>: normalize `code` in `goal` (alias: simpl).",
     "split <goal> <var>: case-split the variable of name `var` in `goal`.",
     "type <goal>: show the type of the `goal`.",
+    "refine <goal> [expr]: refine the `goal` with `expr` (a function or constructor), \
+     or introduce a lambda or constructor without `expr`.",
+    "intro <goal>: introduce a lambda or constructor in the `goal`.",
+    "extract <goal> <name> [args]: move the `goal` into a new function `name`, \
+     taking `args`.",
+    "auto <goal> [hints]: search for a proof of the `goal`, with the given `hints`.",
+    "auto-all: search for proofs of all goals.",
+    "solve <goal>: fill the `goal` if it's determined by the constraints.",
+    "solve-all: fill all the goals that are determined by the constraints.",
     "exit: exit the REPL (alias: quit).",
 ];
 
@@ -79,6 +158,16 @@ impl<'a> UserInput<'a> {
         VALUES
     }
 
+    /// Does the command take a goal as its first argument?
+    pub fn takes_goal(command: &str) -> bool {
+        GOAL_COMMANDS.contains(&command)
+    }
+
+    /// Does the command take some code after the goal?
+    pub fn takes_code(command: &str) -> bool {
+        Self::takes_goal(command) && !["type", "context", "solve", "intro"].contains(&command)
+    }
+
     fn trim_and_parse_ip(line: &str, cmd: &str, ok: impl FnOnce(InteractionId) -> Self) -> Self {
         match line.trim_start_matches(cmd).trim().parse::<InteractionId>() {
             Ok(i) => ok(i),
@@ -104,6 +193,56 @@ impl<'a> UserInput<'a> {
             },
         }
     }
+
+    /// Like `trim_and_parse_to_ip_str`, but the string can be omitted.
+    fn trim_and_parse_to_ip_opt_str(
+        line: &'a str,
+        cmd: &str,
+        ok: impl FnOnce(InteractionId, &'a str) -> Self,
+    ) -> Self {
+        let s = line.trim_start_matches(cmd).trim();
+        let (goal, rest) = s.split_once(' ').unwrap_or((s, ""));
+        match goal.parse::<InteractionId>() {
+            Ok(i) => ok(i, rest.trim()),
+            Err(_) => UserInput::Unknown(Some("I cannot parse the goal number.")),
+        }
+    }
+
+    /// `n`, `n..m` or `n-m`, 1-indexed.
+    fn parse_lines(s: &str) -> Option<(usize, usize)> {
+        let (first, last) = s
+            .split_once("..")
+            .or_else(|| s.split_once('-'))
+            .unwrap_or((s, s));
+        let first = first.trim().parse().ok()?;
+        let last = last.trim().parse().ok()?;
+        Some((first, last)).filter(|_| first >= 1 && first <= last)
+    }
+
+    /// `<n> <line>`, with the leading whitespaces of `line` preserved.
+    fn parse_line_text(line: &'a str, cmd: &str, ok: impl FnOnce(usize, &'a str) -> Self) -> Self {
+        let args = line.trim_start_matches(cmd).trim_start();
+        let (n, text) = args.split_once(' ').unwrap_or((args, ""));
+        match n.parse() {
+            Ok(n) if n >= 1 => ok(n, text),
+            _ => UserInput::Unknown(Some("I cannot parse the line number.")),
+        }
+    }
+
+    /// `[goal] <str>`, the goal can be omitted.
+    fn parse_opt_ip_str(
+        args: &'a str,
+        ok: impl FnOnce(Option<InteractionId>, &'a str) -> Self,
+    ) -> Self {
+        let args = args.trim();
+        match args.split_once(' ') {
+            Some((goal, rest)) => match goal.parse::<InteractionId>() {
+                Ok(i) => ok(Some(i), rest.trim()),
+                Err(_) => ok(None, args),
+            },
+            None => ok(None, args),
+        }
+    }
 }
 
 impl<'a> From<&'a str> for UserInput<'a> {
@@ -118,6 +257,22 @@ impl<'a> From<&'a str> for UserInput<'a> {
             Self::trim_and_parse_ip(line, "type", UserInput::Type)
         } else if line.starts_with("context") {
             Self::trim_and_parse_ip(line, "context", UserInput::Context)
+        } else if line.starts_with("line-insert") {
+            Self::parse_line_text(line, "line-insert", UserInput::InsertLine)
+        } else if line.starts_with("line-replace") {
+            Self::parse_line_text(line, "line-replace", UserInput::ReplaceLine)
+        } else if line.starts_with("line-delete") {
+            match Self::parse_lines(line.trim_start_matches("line-delete").trim()) {
+                Some((first, last)) => UserInput::DeleteLines(first, last),
+                None => UserInput::Unknown(Some("I cannot parse the lines.")),
+            }
+        } else if line == "show" {
+            UserInput::ShowLines(None)
+        } else if line.starts_with("show ") {
+            match Self::parse_lines(line.trim_start_matches("show").trim()) {
+                Some(lines) => UserInput::ShowLines(Some(lines)),
+                None => UserInput::Unknown(Some("I cannot parse the lines.")),
+            }
         } else if line.starts_with("line-show") {
             Self::trim_and_parse_ip(line, "line-show", |i| UserInput::ShowLine(i as usize))
         } else if line.starts_with("fill") || line.starts_with("give") {
@@ -130,6 +285,27 @@ impl<'a> From<&'a str> for UserInput<'a> {
             Self::trim_and_parse_to_ip_str(line, "intro-pattern", "", UserInput::IntroPattern)
         } else if line.starts_with("norm") {
             Self::trim_and_parse_to_ip_str(line, "norm", "", UserInput::Normalize)
+        } else if line.starts_with("refine") {
+            Self::trim_and_parse_to_ip_opt_str(line, "refine", UserInput::Refine)
+        } else if line.starts_with("extract") {
+            Self::trim_and_parse_to_ip_str(line, "extract", "", |i, call| {
+                let (name, args) = call.split_once(' ').unwrap_or((call, ""));
+                if name.is_empty() {
+                    UserInput::Unknown(Some("please specify the name of the function."))
+                } else {
+                    UserInput::Extract(i, name, args.trim())
+                }
+            })
+        } else if line.starts_with("intro") {
+            Self::trim_and_parse_ip(line, "intro", UserInput::Intro)
+        } else if line == "auto-all" {
+            UserInput::AutoAll
+        } else if line.starts_with("auto") {
+            Self::trim_and_parse_to_ip_opt_str(line, "auto", UserInput::Auto)
+        } else if line == "solve-all" {
+            UserInput::SolveAll
+        } else if line.starts_with("solve") {
+            Self::trim_and_parse_ip(line, "solve", UserInput::Solve)
         } else if line.starts_with("split") {
             Self::trim_and_parse_to_ip_str(line, "split", "", UserInput::Split)
         } else if line == "reload" {
@@ -140,8 +316,25 @@ impl<'a> From<&'a str> for UserInput<'a> {
             UserInput::DumpProof
         } else if line == "line-pop" {
             UserInput::PopLine
-        } else if line.starts_with("find-in-module") {
-            UserInput::SearchModule(line.trim_start_matches("find-in-module").trim())
+        } else if line.starts_with("module-graph") {
+            let mut args = line.trim_start_matches("module-graph").split_whitespace();
+            UserInput::ModuleGraph(args.next(), args.next())
+        } else if line.starts_with("search-about") {
+            UserInput::SearchAbout(line.trim_start_matches("search-about").trim())
+        } else if line.starts_with("module-contents") || line.starts_with("find-in-module") {
+            let args = line
+                .trim_start_matches("module-contents")
+                .trim_start_matches("find-in-module");
+            Self::parse_opt_ip_str(args, UserInput::ModuleContents)
+        } else if line.starts_with("why-in-scope") {
+            let args = line.trim_start_matches("why-in-scope");
+            Self::parse_opt_ip_str(args, UserInput::WhyInScope)
+        } else if line == "constraints" {
+            UserInput::Constraints
+        } else if line == "metas" {
+            UserInput::Metas
+        } else if line.starts_with("compile") {
+            UserInput::Compile(line.trim_start_matches("compile").trim())
         } else if line == "exit" || line == "quit" {
             UserInput::Exit
         } else if line == "read-to-end" {
@@ -155,3 +348,48 @@ impl<'a> From<&'a str> for UserInput<'a> {
         }
     }
 }
+
+#[cfg(test)]
+mod test {
+    use super::UserInput;
+
+    #[test]
+    fn lines() {
+        let lines = |line| match UserInput::from(line) {
+            UserInput::DeleteLines(first, last) => Some((first, last)),
+            UserInput::ShowLines(lines) => lines,
+            _ => None,
+        };
+        assert_eq!(lines("line-delete 3"), Some((3, 3)));
+        assert_eq!(lines("line-delete 2..4"), Some((2, 4)));
+        assert_eq!(lines("line-delete 2-4"), Some((2, 4)));
+        assert_eq!(lines("line-delete 4-2"), None);
+        assert_eq!(lines("line-delete 0"), None);
+        assert_eq!(lines("line-delete x"), None);
+        assert_eq!(lines("show 5..7"), Some((5, 7)));
+        assert!(matches!(
+            UserInput::from("show"),
+            UserInput::ShowLines(None)
+        ));
+    }
+
+    #[test]
+    fn line_text() {
+        match UserInput::from("line-replace 3   x = 1") {
+            UserInput::ReplaceLine(3, text) => assert_eq!(text, "  x = 1"),
+            other => panic!("{:?}", other),
+        }
+        match UserInput::from("line-insert 1 ") {
+            UserInput::InsertLine(1, text) => assert_eq!(text, ""),
+            other => panic!("{:?}", other),
+        }
+        assert!(matches!(
+            UserInput::from("line-replace x y"),
+            UserInput::Unknown(_)
+        ));
+        assert!(matches!(
+            UserInput::from("line-replace 0 y"),
+            UserInput::Unknown(_)
+        ));
+    }
+}
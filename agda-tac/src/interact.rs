@@ -2,8 +2,10 @@ use std::io::{self, Write};
 
 use rustyline::error::ReadlineError;
 
+use crate::completion::{refresh, SharedCache};
 use crate::editor::CliEditor;
 use crate::file_io::{history_file, Repl};
+use crate::output::is_json;
 use crate::repl::{line, poll_goals};
 
 const LAMBDA_LT: &str = "\u{03bb}> ";
@@ -31,17 +33,29 @@ pub async fn ion(mut agda: Repl) -> io::Result<()> {
         let stdin = io::stdin();
         loop {
             // Be nice. Avoid using unicode in plain repl.
-            print!("> ");
-            io::stdout().flush()?;
+            if !is_json() {
+                print!("> ");
+                io::stdout().flush()?;
+            }
             let mut next = String::new();
-            stdin.read_line(&mut next)?;
+            if stdin.read_line(&mut next)? == 0 {
+                // End of input, like `exit`.
+                line(&mut agda, "exit").await?;
+                break;
+            }
             if line(&mut agda, next.trim()).await? {
                 break;
             }
         }
     } else {
-        let editor = CliEditor {};
-        let mut r = editor.into_editor();
+        let cache = SharedCache::default();
+        refresh(&mut agda, &cache).await?;
+        let editor = CliEditor {
+            cache: cache.clone(),
+            theme: agda.config.theme.clone(),
+            aliases: agda.config.aliases.clone(),
+        };
+        let mut r = editor.into_editor(agda.config.history_size);
         let history_dir = history_file()?;
         if r.load_history(&history_dir).is_err() {
             println!("no previous history in {}.", history_dir.display())
@@ -54,6 +68,7 @@ pub async fn ion(mut agda: Repl) -> io::Result<()> {
                     if line(&mut agda, trim).await? {
                         break;
                     }
+                    refresh(&mut agda, &cache).await?;
                 }
                 Err(ReadlineError::Interrupted) => {}
                 Err(ReadlineError::Eof) => break println!("Interrupted"),
@@ -0,0 +1,243 @@
+use std::ops::Range;
+use std::path::Path;
+
+/// The flavours of (literate) Agda files.
+///
+/// Agda reports positions in the whole file (the text outside code blocks
+/// counts as comments), so the buffer always holds the whole file
+/// and positions from Agda need no translation.
+#[derive(Debug, Clone, Copy, Default, Eq, PartialEq)]
+pub enum Literate {
+    /// `.agda`, everything is code.
+    #[default]
+    Plain,
+    /// `.lagda.md`, code in ```` ```agda ```` fences.
+    Markdown,
+    /// `.lagda.tex` (or `.lagda`), code in `\begin{code}` environments.
+    TeX,
+    /// `.lagda.rst`, code in indented blocks after `::`.
+    Rst,
+    /// `.lagda.org`, code in `#+begin_src agda2` blocks.
+    Org,
+}
+
+const EXTENSIONS: &[(&str, Literate)] = &[
+    (".lagda.md", Literate::Markdown),
+    (".lagda.tex", Literate::TeX),
+    (".lagda.rst", Literate::Rst),
+    (".lagda.org", Literate::Org),
+    (".lagda", Literate::TeX),
+];
+
+impl Literate {
+    /// The flavour and the file name without the extension,
+    /// `None` if the file is not a literate Agda file.
+    pub fn of_file_name(name: &str) -> Option<(Self, &str)> {
+        EXTENSIONS
+            .iter()
+            .find_map(|(ext, lit)| Some((*lit, name.strip_suffix(ext)?)))
+    }
+
+    pub fn of_path(path: &Path) -> Self {
+        let name = path.file_name().and_then(|name| name.to_str());
+        match name.and_then(Self::of_file_name) {
+            Some((lit, _)) => lit,
+            None => Literate::Plain,
+        }
+    }
+
+    /// Indentation of code lines inside a code block.
+    pub fn code_indent(self) -> &'static str {
+        match self {
+            Literate::Rst => "  ",
+            _ => "",
+        }
+    }
+
+    /// A new code block containing `code`, which doesn't end with a newline.
+    pub fn wrap(self, code: &str) -> String {
+        let indent = self.code_indent();
+        let code: Vec<String> = code.lines().map(|l| format!("{}{}", indent, l)).collect();
+        let code = code.join("\n");
+        match self {
+            Literate::Plain => format!("{}\n", code),
+            Literate::Markdown => format!("```agda\n{}\n```\n", code),
+            Literate::TeX => format!("\\begin{{code}}\n{}\n\\end{{code}}\n", code),
+            Literate::Rst => format!("::\n\n{}\n", code),
+            Literate::Org => format!("#+begin_src agda2\n{}\n#+end_src\n", code),
+        }
+    }
+
+    /// Content of a new file for `module`.
+    pub fn template(self, module: &str) -> String {
+        let code = self.wrap(&format!("module {} where", module));
+        match self {
+            Literate::Plain | Literate::TeX => code,
+            Literate::Markdown => format!("# {}\n\n{}", module, code),
+            Literate::Rst => format!("{}\n{}\n\n{}", module, "=".repeat(module.len()), code),
+            Literate::Org => format!("* {}\n\n{}", module, code),
+        }
+    }
+
+    /// Lines (0-indexed) inside the code blocks, excluding the delimiters.
+    pub fn code_blocks(self, source: &str) -> Vec<Range<usize>> {
+        let lines: Vec<&str> = source.split('\n').collect();
+        match self {
+            Literate::Plain => std::iter::once(0..lines.len()).collect(),
+            Literate::Markdown => fenced(&lines, |l| {
+                let lang = l.strip_prefix("```")?.trim();
+                let is_agda = lang.is_empty() || lang == "agda";
+                Some((is_agda, |l: &str| l == "```"))
+            }),
+            Literate::TeX => fenced(&lines, |l| {
+                l.strip_prefix("\\begin{code}")?;
+                Some((true, |l: &str| l.starts_with("\\end{code}")))
+            }),
+            Literate::Org => fenced(&lines, |l| {
+                let l = l.to_lowercase();
+                let lang = l.strip_prefix("#+begin_src")?.trim_start();
+                let is_agda = lang.split_whitespace().next() == Some("agda2");
+                Some((is_agda, |l: &str| l.eq_ignore_ascii_case("#+end_src")))
+            }),
+            Literate::Rst => indented(&lines),
+        }
+    }
+}
+
+/// Whether a block holds Agda code, and how to recognize its closing line.
+type Fence = (bool, fn(&str) -> bool);
+
+/// Code blocks delimited by fence lines, `open` recognizes the opening lines
+/// of all blocks, so that the blocks in other languages are skipped as a whole.
+fn fenced(lines: &[&str], open: impl Fn(&str) -> Option<Fence>) -> Vec<Range<usize>> {
+    let mut blocks = Vec::new();
+    let mut i = 0;
+    while i < lines.len() {
+        let (is_agda, close) = match open(lines[i].trim()) {
+            Some(fence) => fence,
+            None => {
+                i += 1;
+                continue;
+            }
+        };
+        let start = i + 1;
+        let end = (start..lines.len())
+            .find(|&j| close(lines[j].trim()))
+            .unwrap_or(lines.len());
+        if is_agda {
+            blocks.push(start..end);
+        }
+        i = end + 1;
+    }
+    blocks
+}
+
+/// Indented blocks after a line ending with `::`, reStructuredText style.
+fn indented(lines: &[&str]) -> Vec<Range<usize>> {
+    let mut blocks = Vec::new();
+    let mut i = 0;
+    while i < lines.len() {
+        if !lines[i].trim_end().ends_with("::") {
+            i += 1;
+            continue;
+        }
+        let start = i + 1;
+        let mut end = start;
+        for (j, line) in lines.iter().enumerate().skip(start) {
+            if line.trim().is_empty() {
+                continue;
+            }
+            if !line.starts_with(char::is_whitespace) {
+                break;
+            }
+            end = j + 1;
+        }
+        // The blank line after `::` is not code.
+        let start = (start..end)
+            .find(|&j| !lines[j].trim().is_empty())
+            .unwrap_or(end);
+        blocks.push(start..end);
+        // The line after the block may start another one.
+        i = end.max(start);
+    }
+    blocks
+}
+
+#[cfg(test)]
+mod test {
+    use super::Literate::{self, *};
+
+    const ALL: [Literate; 5] = [Plain, Markdown, TeX, Rst, Org];
+
+    #[test]
+    fn file_names() {
+        assert_eq!(Literate::of_file_name("A.lagda.md"), Some((Markdown, "A")));
+        assert_eq!(Literate::of_file_name("A.lagda"), Some((TeX, "A")));
+        assert_eq!(Literate::of_file_name("A.agda"), None);
+    }
+
+    #[test]
+    fn tex() {
+        let source = "Intro\n\\begin{code}\nmodule A where\n\\end{code}\ntext\n\\begin{code}[hide]\nx = 1\n\\end{code}\n";
+        assert_eq!(TeX.code_blocks(source), vec![2..3, 6..7]);
+    }
+
+    #[test]
+    fn markdown() {
+        let source =
+            "# A\n\n```agda\nmodule A where\n```\n\n```haskell\nx\n```\n\n```\ny = 1\n```\n";
+        assert_eq!(Markdown.code_blocks(source), vec![3..4, 11..12]);
+    }
+
+    #[test]
+    fn rst() {
+        let source = "A\n=\n\nCode::\n\n  module A where\n\n  x = 1\n\nText\n";
+        assert_eq!(Rst.code_blocks(source), vec![5..8]);
+    }
+
+    #[test]
+    fn org() {
+        let source = "* A\n\n#+BEGIN_SRC agda2\nmodule A where\n#+END_SRC\n#+begin_src haskell\nx\n#+end_src\n";
+        assert_eq!(Org.code_blocks(source), vec![3..4]);
+    }
+
+    #[test]
+    fn empty() {
+        assert_eq!(Plain.code_blocks(""), vec![0..1]);
+        for lit in &ALL[1..] {
+            assert_eq!(lit.code_blocks(""), vec![]);
+        }
+    }
+
+    #[test]
+    fn no_trailing_newline() {
+        assert_eq!(
+            TeX.code_blocks("\\begin{code}\nx = 1\n\\end{code}"),
+            vec![1..2]
+        );
+        // Unclosed, the rest of the file is code.
+        assert_eq!(Markdown.code_blocks("```agda\nx = 1\ny = 2"), vec![1..3]);
+        assert_eq!(Rst.code_blocks("::\n\n  x = 1"), vec![2..3]);
+    }
+
+    #[test]
+    fn templates() {
+        for lit in &ALL {
+            let source = lit.template("A.B");
+            let lines: Vec<&str> = source.split('\n').collect();
+            let blocks = lit.code_blocks(&source);
+            let block = blocks.last().unwrap().clone();
+            let code: Vec<&str> = lines[block].iter().map(|l| l.trim()).collect();
+            assert_eq!(code[0], "module A.B where", "{:?}", lit);
+            let wrapped = format!("{}{}", source, lit.wrap("x = 1\ny = 2"));
+            let lines: Vec<&str> = wrapped.split('\n').collect();
+            let block = lit.code_blocks(&wrapped).last().unwrap().clone();
+            let code: Vec<&str> = lines[block]
+                .iter()
+                .map(|l| l.trim())
+                .filter(|l| !l.is_empty())
+                .collect();
+            assert!(code.ends_with(&["x = 1", "y = 2"]), "{:?}: {:?}", lit, code);
+        }
+    }
+}
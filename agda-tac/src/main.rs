@@ -5,22 +5,43 @@ use agda_mode::debug::{
     debug_command_via, debug_response_via, dont_debug_command, dont_debug_response,
 };
 
+use crate::config::Config;
 use crate::file_io::{find_default_unwrap, InitModule, Repl};
 
 /// Clap cli argument things.
 mod args;
+/// Completion candidates, cached per load.
+mod completion;
+/// Configuration file.
+mod config;
 /// Rustyline completion & hints & things.
 mod editor;
 /// Buffer & file, for Agda interaction.
 mod file_io;
+/// Agda-aware highlighting of the input line.
+mod highlight;
+/// Targeted hints for common Agda errors.
+mod hint;
 /// Parse user input as a structural "command".
 mod input;
 /// Basic info about interaction, like `help`, read line & print things, etc.
 mod interact;
+/// Literate Agda files, where the code lives in code blocks.
+mod literate;
+/// Command results, printed as text or JSON.
+mod output;
+/// Live loading progress.
+mod progress;
+/// The library of the file.
+mod project;
 /// Implementation of interaction.
 mod repl;
+/// Run REPL commands from a file, non-interactively.
+mod script;
 
 const FAIL_WRITE: &str = "Failed to create Agda module file";
+const FAIL_CONFIG: &str = "Failed to read the configuration";
+const FAIL_LIBRARY: &str = "Failed to read the Agda libraries";
 const FAIL: &str = "Failed to start Agda";
 const FAIL_CMD: &str = "Failed to evaluate Agda command";
 
@@ -38,10 +59,38 @@ async fn main() {
     } else {
         dont_debug_response()
     }
-    let agda_program = args.agda.clone().unwrap_or_else(|| PathBuf::from("agda"));
+    if args.output == "json" {
+        output::use_json();
+    }
     let file = args.file.unwrap_or_else(find_default_unwrap);
+    let file = std::path::absolute(&file).expect(FAIL_WRITE);
+    let config = match Config::load(&file).expect(FAIL_CONFIG) {
+        Ok(config) => config,
+        Err(e) => {
+            eprintln!("Invalid configuration: {}", e);
+            std::process::exit(1);
+        }
+    };
+    let load = config.load.clone().merge(args.load);
+    let flags = match load.flags() {
+        Ok(flags) => flags,
+        Err(e) => {
+            eprintln!("Invalid Agda options: {}", e);
+            std::process::exit(1);
+        }
+    };
+    let project = project::check(&file, &load.library).expect(FAIL_LIBRARY);
+    if let (Some(root), false) = (project.root(), output::is_json()) {
+        println!("Project root: {}", root.display());
+    }
     let InitModule(f, path, init) =
-        file_io::init_module(file, args.allow_existing_file).expect(FAIL_WRITE);
+        match file_io::init_module(file, &project, args.allow_existing_file) {
+            Ok(init) => init,
+            Err(e) => {
+                eprintln!("{}: {}", FAIL_WRITE, e);
+                std::process::exit(1);
+            }
+        };
     // Resolve path to an absolute PathBuf (canonical if possible)
     let abs_path = match path.canonicalize() {
         Ok(p) => p,
@@ -50,13 +99,30 @@ async fn main() {
             std::process::exit(1);
         }
     };
-    let mut repl_state = ReplState::start(&agda_program, abs_path).await.expect(FAIL);
+    let agda_program = args.agda.or_else(|| config.agda_path.clone());
+    let agda_program = agda_program.unwrap_or_else(|| PathBuf::from("agda"));
+    let mut repl_state = ReplState::start_with(&agda_program, &config.agda_args, abs_path, flags)
+        .await
+        .expect(FAIL);
     if args.validate {
         repl_state.validate_version_panicking().await;
         println!("It works!");
         std::process::exit(0);
     }
+    // JSON output is for programs, not for humans.
+    let plain = args.plain || config.plain || output::is_json();
+    if !plain && args.script.is_none() {
+        progress::show_progress(&mut repl_state);
+    }
     let mut repl_state = Repl::new(repl_state, f, path, init);
-    repl_state.is_plain = args.plain;
+    repl_state.is_plain = plain;
+    repl_state.config = config;
+    repl_state.project = project;
+    if let Some(script) = args.script {
+        let code = script::run(repl_state, &script, args.proof.as_deref())
+            .await
+            .expect(FAIL_CMD);
+        std::process::exit(code);
+    }
     interact::ion(repl_state).await.expect(FAIL_CMD);
 }
@@ -0,0 +1,463 @@
+use std::sync::atomic::{AtomicBool, Ordering};
+
+use serde::Serialize;
+
+use agda_mode::diag::{Diagnostic, Severity};
+use agda_mode::pos::{InteractionId, ProblemId};
+
+use crate::hint::{hint, print_hint};
+
+static JSON: AtomicBool = AtomicBool::new(false);
+/// Was anything reported for the current command?
+static REPORTED: AtomicBool = AtomicBool::new(false);
+/// Was an error reported for the current command?
+static FAILED: AtomicBool = AtomicBool::new(false);
+
+/// Print one JSON object per command instead of text.
+pub fn use_json() {
+    JSON.store(true, Ordering::Relaxed)
+}
+
+pub fn is_json() -> bool {
+    JSON.load(Ordering::Relaxed)
+}
+
+#[derive(Serialize, Debug, Clone)]
+pub struct GoalEntry {
+    pub id: InteractionId,
+    pub r#type: Option<String>,
+    /// Only in [`Report::GoalList`].
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub line: Option<usize>,
+}
+
+/// A definition and its type.
+#[derive(Serialize, Debug, Clone)]
+pub struct NamedEntry {
+    pub name: String,
+    pub term: String,
+}
+
+/// A line of the file, with the goals in it.
+#[derive(Serialize, Debug, Clone)]
+pub struct NumberedLine {
+    pub line: usize,
+    pub text: String,
+    pub goals: Vec<InteractionId>,
+}
+
+/// A constraint (or an unsolved meta) and where it comes from.
+#[derive(Serialize, Debug, Clone)]
+pub struct ConstraintEntry {
+    pub constraint: String,
+    /// The line in the current file.
+    pub line: Option<usize>,
+    /// `file:line,col`, if it's in another file.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub location: Option<String>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub problems: Vec<ProblemId>,
+}
+
+#[derive(Serialize, Debug, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct ContextEntry {
+    pub name: String,
+    pub binding: String,
+    pub in_scope: bool,
+}
+
+/// The result of a command.
+#[derive(Serialize, Debug, Clone)]
+#[serde(tag = "kind", rename_all = "camelCase")]
+pub enum Report {
+    /// The goals after loading.
+    #[serde(rename_all = "camelCase")]
+    Goals {
+        goals: Vec<GoalEntry>,
+        unsolved_metas: Vec<String>,
+        diagnostics: Vec<Diagnostic>,
+    },
+    /// The goals known to the REPL, without reloading.
+    GoalList {
+        goals: Vec<GoalEntry>,
+        stale: bool,
+    },
+    NormalForm {
+        input: String,
+        expr: String,
+    },
+    InferredType {
+        input: String,
+        r#type: String,
+    },
+    GoalType {
+        goal: InteractionId,
+        r#type: String,
+    },
+    Context {
+        goal: InteractionId,
+        entries: Vec<ContextEntry>,
+    },
+    /// Agda doesn't know which constructor to introduce.
+    Constructors {
+        goal: InteractionId,
+        constructors: Vec<String>,
+    },
+    /// Result of `auto` and `solve`, the solved goals are filled.
+    Solved {
+        solved: Vec<InteractionId>,
+        unsolved: Vec<InteractionId>,
+        message: Option<String>,
+        /// The goals (or the error) after reloading, if the file needed it.
+        #[serde(skip_serializing_if = "Option::is_none")]
+        reload: Option<Box<Report>>,
+    },
+    /// The goal is filled (or split), the file is updated.
+    Edited {
+        goal: InteractionId,
+        /// The goals (or the error) after reloading, if the file needed it.
+        #[serde(skip_serializing_if = "Option::is_none")]
+        reload: Option<Box<Report>>,
+    },
+    Line {
+        line: usize,
+        text: String,
+    },
+    Proof {
+        text: String,
+    },
+    /// Lines of the file, see [`NumberedLine`].
+    Lines {
+        lines: Vec<NumberedLine>,
+    },
+    SearchAbout {
+        search: String,
+        results: Vec<NamedEntry>,
+    },
+    ModuleContents {
+        module: String,
+        /// The module parameters, like `{A : Set}`.
+        telescope: Vec<String>,
+        /// Sub-modules.
+        modules: Vec<String>,
+        contents: Vec<NamedEntry>,
+    },
+    WhyInScope {
+        name: String,
+        /// Where `name` is defined.
+        file: String,
+        message: String,
+    },
+    Constraints {
+        constraints: Vec<ConstraintEntry>,
+    },
+    /// The goals and unsolved metas, with their lines.
+    Metas {
+        goals: Vec<GoalEntry>,
+        metas: Vec<ConstraintEntry>,
+    },
+    /// The backend's own warnings and errors.
+    Compiled {
+        backend: String,
+        warnings: String,
+        errors: String,
+    },
+    /// Free-form output, like the help or an exported graph.
+    Text {
+        text: String,
+    },
+    Error {
+        message: String,
+        diagnostic: Diagnostic,
+        hint: Option<String>,
+    },
+    /// Nothing else to say.
+    Ok,
+}
+
+impl Report {
+    pub fn error(message: String) -> Self {
+        let diagnostic = Diagnostic::parse(Severity::Error, &message);
+        let hint = hint(&diagnostic.kind());
+        Report::Error {
+            message,
+            diagnostic,
+            hint,
+        }
+    }
+
+    pub fn text(text: impl Into<String>) -> Self {
+        Report::Text { text: text.into() }
+    }
+
+    /// Is this an error, or does it contain one?
+    pub fn failed(&self) -> bool {
+        match self {
+            Report::Error { .. } => true,
+            Report::Solved {
+                reload: Some(reload),
+                ..
+            }
+            | Report::Edited {
+                reload: Some(reload),
+                ..
+            } => reload.failed(),
+            _ => false,
+        }
+    }
+
+    fn print_text(&self) {
+        use Report::*;
+        match self {
+            Goals {
+                goals,
+                unsolved_metas,
+                diagnostics,
+            } => {
+                if goals.is_empty() {
+                    println!("No goals.");
+                } else {
+                    println!("Goals:");
+                }
+                for goal in goals {
+                    let ty = goal.r#type.as_deref().unwrap_or("?");
+                    println!("?{} : {}", goal.id, ty);
+                }
+                if !unsolved_metas.is_empty() {
+                    println!("Unsolved metas:");
+                }
+                for meta in unsolved_metas {
+                    println!("{}", meta);
+                }
+                for diagnostic in diagnostics {
+                    eprintln!("{}", diagnostic);
+                    if diagnostic.severity == Severity::Error {
+                        print_hint(&diagnostic.kind());
+                    }
+                }
+            }
+            GoalList { goals, stale } => {
+                if goals.is_empty() {
+                    println!("No goals, you're all set.");
+                }
+                for goal in goals {
+                    let line = goal.line.unwrap_or_default();
+                    match &goal.r#type {
+                        Some(ty) => println!("?{} : {} at line {}", goal.id, ty, line),
+                        None => println!("?{} at line {}", goal.id, line),
+                    }
+                }
+                if *stale {
+                    println!("The file changed since the last load, `reload` to update the goals.");
+                }
+            }
+            NormalForm { input, expr } => println!("{} --> {}", input, expr),
+            InferredType { input, r#type } => println!("{} : {}", input, r#type),
+            GoalType { r#type, .. } => println!("{}", r#type),
+            Context { entries, .. } => {
+                if entries.is_empty() {
+                    println!("Context is empty, oops.");
+                }
+                for entry in entries {
+                    if !entry.in_scope {
+                        print!("(Not in scope) ")
+                    }
+                    println!("{} : {}", entry.name, entry.binding);
+                }
+            }
+            SearchAbout { search, results } => {
+                if results.is_empty() {
+                    println!("Nothing found about `{}`.", search);
+                }
+                print_entries(results);
+            }
+            ModuleContents {
+                module,
+                telescope,
+                modules,
+                contents,
+            } => {
+                if !telescope.is_empty() {
+                    println!("Module parameters: {}", telescope.join(" "));
+                }
+                if !modules.is_empty() {
+                    println!("Modules:");
+                    for module in modules {
+                        println!("  {}", module);
+                    }
+                }
+                if contents.is_empty() {
+                    println!("Nothing in `{}`.", module);
+                } else {
+                    println!("Names:");
+                }
+                print_entries(contents);
+            }
+            WhyInScope { message, .. } => println!("{}", message.trim_end()),
+            Constraints { constraints } => {
+                if constraints.is_empty() {
+                    println!("No constraints.");
+                }
+                print_constraints(constraints);
+            }
+            Metas { goals, metas } => {
+                if goals.is_empty() && metas.is_empty() {
+                    println!("No goals or metas.");
+                }
+                if !goals.is_empty() {
+                    println!("Goals:");
+                }
+                for goal in goals {
+                    let ty = goal.r#type.as_deref().unwrap_or("?");
+                    match goal.line {
+                        Some(line) => println!("  line {}: ?{} : {}", line, goal.id, ty),
+                        None => println!("  ?{} : {}", goal.id, ty),
+                    }
+                }
+                if !metas.is_empty() {
+                    println!("Unsolved metas:");
+                }
+                print_constraints(metas);
+            }
+            Compiled {
+                backend,
+                warnings,
+                errors,
+            } => {
+                println!("Compiled with {}.", backend);
+                if !warnings.trim().is_empty() {
+                    println!("Warnings:\n{}", warnings.trim_end());
+                }
+                if !errors.trim().is_empty() {
+                    eprintln!("Errors:\n{}", errors.trim_end());
+                }
+            }
+            Lines { lines } => {
+                let marker = |line: &NumberedLine| {
+                    let goals: Vec<String> = line.goals.iter().map(|i| format!("?{}", i)).collect();
+                    goals.join(" ")
+                };
+                let number_width = lines.last().map_or(1, |l| l.line.to_string().len());
+                let marker_width = lines.iter().map(|l| marker(l).len()).max();
+                // With a space after the goals, if any.
+                let marker_width = marker_width.map_or(0, |w| if w == 0 { 0 } else { w + 1 });
+                for line in lines {
+                    println!(
+                        "{:>nw$} {:<mw$}| {}",
+                        line.line,
+                        marker(line),
+                        line.text,
+                        nw = number_width,
+                        mw = marker_width
+                    );
+                }
+            }
+            Line { text, .. } | Proof { text } | Text { text } => print!("{}", text),
+            Error { message, hint, .. } => {
+                eprintln!("Errors:\n{}", message);
+                if let Some(hint) = hint {
+                    eprintln!("Hint: {}", hint);
+                }
+            }
+            Constructors { goal, constructors } => {
+                println!(
+                    "Which constructor? Try `refine {} <constructor>` with:",
+                    goal
+                );
+                for constructor in constructors {
+                    println!("  {}", constructor);
+                }
+            }
+            Solved {
+                solved,
+                unsolved,
+                message,
+                reload,
+            } => {
+                let goals = |ids: &[InteractionId]| {
+                    let ids: Vec<String> = ids.iter().map(|i| format!("?{}", i)).collect();
+                    ids.join(", ")
+                };
+                if !solved.is_empty() {
+                    println!("Solved {}.", goals(solved));
+                }
+                if !unsolved.is_empty() {
+                    println!("Not solved: {}.", goals(unsolved));
+                }
+                if let Some(message) = message {
+                    println!("{}", message);
+                }
+                if let Some(reload) = reload {
+                    reload.print_text();
+                }
+            }
+            Edited { reload, .. } => {
+                if let Some(reload) = reload {
+                    reload.print_text();
+                }
+            }
+            Ok => {}
+        }
+    }
+}
+
+/// `name : type` lines, with the colons aligned.
+fn print_entries(entries: &[NamedEntry]) {
+    let width = entries.iter().map(|e| e.name.chars().count()).max();
+    let width = width.unwrap_or_default();
+    for entry in entries {
+        let pad = width - entry.name.chars().count();
+        let indent = " ".repeat(width + 5);
+        let term = entry.term.replace('\n', &format!("\n{}", indent));
+        println!("  {}{} : {}", entry.name, " ".repeat(pad), term);
+    }
+}
+
+/// One constraint per line, prefixed with its line and followed by its problems.
+fn print_constraints(constraints: &[ConstraintEntry]) {
+    for entry in constraints {
+        match (entry.line, &entry.location) {
+            (Some(line), _) => print!("  line {}: {}", line, entry.constraint),
+            (None, Some(location)) => print!("  {}: {}", location, entry.constraint),
+            (None, None) => print!("  {}", entry.constraint),
+        }
+        if entry.problems.is_empty() {
+            println!();
+        } else {
+            let problems: Vec<String> = entry.problems.iter().map(|p| p.to_string()).collect();
+            println!("  (problems {})", problems.join(", "));
+        }
+    }
+}
+
+/// Print the result of a command, as text or JSON.
+pub fn report(report: Report) {
+    REPORTED.store(true, Ordering::Relaxed);
+    if report.failed() {
+        FAILED.store(true, Ordering::Relaxed);
+    }
+    if is_json() {
+        println!("{}", serde_json::to_string(&report).unwrap());
+    } else {
+        report.print_text()
+    }
+}
+
+/// Called before each command.
+pub fn begin_command() {
+    REPORTED.store(false, Ordering::Relaxed);
+    FAILED.store(false, Ordering::Relaxed)
+}
+
+/// Did the current command fail, either in Agda or before reaching it?
+pub fn command_failed() -> bool {
+    FAILED.load(Ordering::Relaxed)
+}
+
+/// Called after each command, so that every command reports something in JSON.
+pub fn end_command() {
+    if is_json() && !REPORTED.load(Ordering::Relaxed) {
+        report(Report::Ok)
+    }
+}
@@ -0,0 +1,57 @@
+use std::io::{self, Write};
+use std::sync::atomic::{AtomicBool, Ordering};
+
+use agda_mode::agda::ReplState;
+use agda_mode::resp::Progress;
+
+/// `Checking`/`Finished` messages are of debug level 1.
+const PROGRESS_LEVEL: i32 = 1;
+
+/// Is there a progress line to be cleared?
+static LINE_ACTIVE: AtomicBool = AtomicBool::new(false);
+
+fn status_line(text: &str) {
+    let mut err = io::stderr();
+    let _ = write!(err, "\r\x1b[K{}", text);
+    let _ = err.flush();
+    LINE_ACTIVE.store(true, Ordering::Relaxed);
+}
+
+/// Remove the progress line, if any.
+pub fn clear_line() {
+    if LINE_ACTIVE.swap(false, Ordering::Relaxed) {
+        let mut err = io::stderr();
+        let _ = write!(err, "\r\x1b[K");
+        let _ = err.flush();
+    }
+}
+
+/// Show a live "checking X of N modules" line while Agda loads the file.
+/// N is the number of modules Agda started checking so far.
+pub fn show_progress(agda: &mut ReplState) {
+    let mut started = 0usize;
+    let mut finished = 0usize;
+    agda.on_progress(PROGRESS_LEVEL, move |progress| match progress {
+        Progress::Checking { module, .. } => {
+            started += 1;
+            let current = (finished + 1).min(started);
+            status_line(&format!(
+                "checking {} of {} modules: {}",
+                current, started, module
+            ));
+        }
+        Progress::Finished { module, .. } => {
+            finished += 1;
+            status_line(&format!(
+                "checked {} of {} modules: {}",
+                finished, started, module
+            ));
+        }
+        Progress::Clear => {
+            started = 0;
+            finished = 0;
+            clear_line();
+        }
+        _ => {}
+    })
+}
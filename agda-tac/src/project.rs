@@ -0,0 +1,67 @@
+use std::path::{Path, PathBuf};
+
+use agda_mode::library::{module_name, AgdaLib, Libraries};
+
+use crate::file_io::Monad;
+
+/// The library containing the file, and where its modules are.
+#[derive(Debug, Clone, Default)]
+pub struct Project {
+    pub library: Option<AgdaLib>,
+    /// Of the library and its dependencies.
+    pub include_paths: Vec<PathBuf>,
+}
+
+impl Project {
+    /// The directory of the `.agda-lib` file.
+    pub fn root(&self) -> Option<&Path> {
+        self.library.as_ref().map(AgdaLib::root)
+    }
+
+    /// The qualified name of the module in `file`, like `Data.Nat` for `src/Data/Nat.agda`,
+    /// preferring the include directories of the library to the ones of its dependencies.
+    /// Outside of a library, it's the file name.
+    pub fn module_name(&self, file: &Path) -> Option<String> {
+        let own = self.library.as_ref().map(AgdaLib::include_paths);
+        let own = own.and_then(|include_paths| module_name(&include_paths, file));
+        own.or_else(|| module_name(&self.include_paths, file))
+            .or_else(|| {
+                let parent = file.parent()?.to_owned();
+                module_name(&[parent], file)
+            })
+    }
+}
+
+/// Find the library of `file`, and warn about the libraries Agda won't find,
+/// including the ones given by `--library`.
+pub fn check(file: &Path, extra_libraries: &[String]) -> Monad<Project> {
+    let libraries = match Libraries::agda_dir() {
+        Some(dir) => Libraries::read(&dir)?,
+        None => Default::default(),
+    };
+    for error in &libraries.errors {
+        eprintln!("Cannot read library {}", error);
+    }
+    let resolution = match libraries.resolve(file)? {
+        Ok(resolution) => resolution,
+        Err(e) => {
+            eprintln!("Invalid library file: {}", e);
+            return Ok(Default::default());
+        }
+    };
+    let mut extra = libraries.resolve_names(extra_libraries);
+    let mut missing = resolution.missing;
+    missing.append(&mut extra.missing);
+    if !missing.is_empty() {
+        eprintln!(
+            "Libraries not found in the `libraries` file: {}.",
+            missing.join(", ")
+        );
+    }
+    let mut include_paths = resolution.include_paths;
+    include_paths.append(&mut extra.include_paths);
+    Ok(Project {
+        library: resolution.library,
+        include_paths,
+    })
+}
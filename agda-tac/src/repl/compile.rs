@@ -0,0 +1,24 @@
+use agda_mode::cmd::{Backend, Cmd};
+
+use crate::file_io::{Monad, Repl};
+use crate::hint::preprint_with_hint;
+use crate::output::{report, Report};
+
+/// Compile the file with `backend`, passing the load flags and `flags` to Agda.
+pub async fn compile(agda: &mut Repl, backend: Backend, flags: Vec<String>) -> Monad {
+    let name = backend.name().to_owned();
+    let file = agda.agda.file.clone();
+    let mut all_flags = agda.agda.load_flags.clone();
+    all_flags.extend(flags);
+    agda.agda
+        .command(Cmd::compile(backend, file, all_flags))
+        .await?;
+    if let Some((warnings, errors)) = preprint_with_hint(agda.agda.next_compilation_ok().await?) {
+        report(Report::Compiled {
+            backend: name,
+            warnings,
+            errors,
+        });
+    }
+    Ok(())
+}
@@ -1,12 +1,19 @@
-use either::Either;
-
-use agda_mode::agda::preprint_agda_result;
 use agda_mode::base::ComputeMode;
-use agda_mode::cmd::{Cmd, GoalInput};
+use agda_mode::cmd::{Cmd, GoalInput, InputWithRewrite};
+use agda_mode::edit;
 use agda_mode::pos::InteractionId;
-use agda_mode::resp::{Context, GoalInfo, MakeCase, MakeCaseVariant};
+use agda_mode::resp::{Context, GoalInfo, MakeCase};
 
 use crate::file_io::{Monad, Repl};
+use crate::hint::preprint_with_hint;
+use crate::output::{report, ContextEntry, Report};
+
+fn with_rewrite(agda: &Repl, input: GoalInput) -> InputWithRewrite {
+    InputWithRewrite {
+        rewrite: agda.config.rewrite,
+        input,
+    }
+}
 
 pub async fn norm(agda: &mut Repl, i: InteractionId, new: &str, mode: ComputeMode) -> Monad {
     let command = Cmd::Compute {
@@ -14,9 +21,12 @@ pub async fn norm(agda: &mut Repl, i: InteractionId, new: &str, mode: ComputeMod
         input: GoalInput::no_range(i, new.to_owned()),
     };
     agda.agda.command(command).await?;
-    if let Some(gs) = preprint_agda_result(agda.agda.next_goal_specific().await?) {
+    if let Some(gs) = preprint_with_hint(agda.agda.next_goal_specific().await?) {
         match gs.goal_info {
-            GoalInfo::NormalForm { expr, .. } => println!("{} --> {}", new, expr),
+            GoalInfo::NormalForm { expr, .. } => report(Report::NormalForm {
+                input: new.to_owned(),
+                expr,
+            }),
             _ => unreachable!(),
         }
     }
@@ -26,11 +36,16 @@ pub async fn norm(agda: &mut Repl, i: InteractionId, new: &str, mode: ComputeMod
 pub async fn give(agda: &mut Repl, i: InteractionId, new: &str) -> Monad {
     let command = Cmd::give(GoalInput::no_range(i, new.to_owned()));
     agda.agda.command(command).await?;
-    if let Some(gs) = preprint_agda_result(agda.agda.next_give_action().await?) {
-        match gs.give_result.into_either() {
-            Either::Left(s) => agda.fill_goal_buffer(gs.interaction_point, &s),
-            // Don't know yet what to do
-            Either::Right(_b) => unimplemented!(),
+    if let Some(gs) = preprint_with_hint(agda.agda.next_give_action().await?) {
+        match edit::give(&gs, new) {
+            Ok(edits) => {
+                agda.apply_edits(&edits);
+                report(Report::Edited {
+                    goal: i,
+                    reload: None,
+                });
+            }
+            Err(e) => report(Report::error(e)),
         }
         agda.sync_buffer()?;
         // Poll the goals' information
@@ -40,11 +55,14 @@ pub async fn give(agda: &mut Repl, i: InteractionId, new: &str) -> Monad {
 }
 
 pub async fn infer(agda: &mut Repl, i: InteractionId, new: &str) -> Monad {
-    let command = Cmd::infer(GoalInput::no_range(i, new.to_owned()));
+    let command = Cmd::Infer(with_rewrite(agda, GoalInput::no_range(i, new.to_owned())));
     agda.agda.command(command).await?;
-    if let Some(gs) = preprint_agda_result(agda.agda.next_goal_specific().await?) {
+    if let Some(gs) = preprint_with_hint(agda.agda.next_goal_specific().await?) {
         match gs.goal_info {
-            GoalInfo::InferredType { expr } => println!("{} : {}", new, expr),
+            GoalInfo::InferredType { expr } => report(Report::InferredType {
+                input: new.to_owned(),
+                r#type: expr,
+            }),
             _ => unreachable!(),
         }
     }
@@ -54,51 +72,50 @@ pub async fn infer(agda: &mut Repl, i: InteractionId, new: &str) -> Monad {
 pub async fn split(agda: &mut Repl, i: InteractionId, pat: &str) -> Monad {
     let command = Cmd::split(GoalInput::no_range(i, pat.to_owned()));
     agda.agda.command(command).await?;
-    if let Some(mk) = preprint_agda_result(agda.agda.next_make_case().await?) {
+    if let Some(mk) = preprint_with_hint(agda.agda.next_make_case().await?) {
         let mk: MakeCase = mk;
-        match mk.variant {
-            MakeCaseVariant::Function => {
-                let start = mk.interaction_point.the_interval().start;
-                let line = start.line;
-                // Double-check the position.
-                // Note Agda uses 1-indexed line numbers.
-                debug_assert_eq!(agda.line_of_offset(start.pos) + 1, line);
-                agda.remove_line_buffer(line);
-                for clause in mk.clauses.into_iter().rev() {
-                    agda.insert_line_buffer(line, &clause);
-                }
+        match edit::make_case(&agda.source(), &mk) {
+            Ok(edits) => {
+                agda.apply_edits(&edits);
+                // Agda needs a reload to see the new clauses.
+                agda.agda.mark_buffer_changed();
+                agda.sync_buffer()?;
+                report(Report::Edited {
+                    goal: i,
+                    reload: None,
+                });
             }
-            MakeCaseVariant::ExtendedLambda => unimplemented!(),
+            Err(e) => report(Report::error(e)),
         }
-        agda.sync_buffer()?;
     }
     Ok(())
 }
 
 pub async fn ctx(agda: &mut Repl, i: InteractionId) -> Monad {
-    let command = Cmd::context(GoalInput::simple(i));
+    let command = Cmd::Context(with_rewrite(agda, GoalInput::simple(i)));
     agda.agda.command(command).await?;
-    if let Some(ctx) = preprint_agda_result(agda.agda.next_context().await?) {
+    if let Some(ctx) = preprint_with_hint(agda.agda.next_context().await?) {
         let ctx: Context = ctx;
-        if ctx.context.is_empty() {
-            println!("Context is empty, oops.");
-        }
-        for entry in ctx.context {
-            if !entry.in_scope {
-                print!("(Not in scope) ")
-            }
-            println!("{} : {}", entry.original_name, entry.binding);
-        }
+        let entries = ctx
+            .context
+            .into_iter()
+            .map(|entry| ContextEntry {
+                name: entry.original_name,
+                binding: entry.binding,
+                in_scope: entry.in_scope,
+            })
+            .collect();
+        report(Report::Context { goal: i, entries });
     }
     Ok(())
 }
 
 pub async fn ty(agda: &mut Repl, i: InteractionId) -> Monad {
-    let command = Cmd::goal_type(GoalInput::simple(i));
+    let command = Cmd::GoalType(with_rewrite(agda, GoalInput::simple(i)));
     agda.agda.command(command).await?;
-    if let Some(gs) = preprint_agda_result(agda.agda.next_goal_specific().await?) {
+    if let Some(gs) = preprint_with_hint(agda.agda.next_goal_specific().await?) {
         match gs.goal_info {
-            GoalInfo::CurrentGoal { r#type, .. } => println!("{}", r#type),
+            GoalInfo::CurrentGoal { r#type, .. } => report(Report::GoalType { goal: i, r#type }),
             _ => unreachable!(),
         }
     }
@@ -1,5 +1,7 @@
 use crate::file_io::{Monad, Repl};
-use agda_mode::agda::{preprint_agda_result, ReplState};
+use crate::output::{report, GoalEntry, Report};
+use crate::progress::clear_line;
+use agda_mode::agda::ReplState;
 
 pub async fn reload_unit(agda: &mut Repl) -> Monad {
     reload(agda).await.map(|_| ())
@@ -12,28 +14,47 @@ pub async fn reload(agda: &mut Repl) -> Monad<bool> {
 }
 
 pub async fn poll_goals(agda: &mut ReplState) -> Monad<bool> {
-    if let Some(agw) = preprint_agda_result(agda.next_all_goals_warnings().await?) {
-        if agw.visible_goals.is_empty() {
-            println!("No goals.");
-        } else {
-            println!("Goals:");
-        }
-        for goal in agw.visible_goals {
-            // I believe `goal` will always be `OfType`.
-            match goal.try_as_of_type() {
-                Ok(ok) => println!("?{} : {}", ok.constraint_obj, ok.r#type),
-                Err(bad) => eprintln!("[WARN]: unexpected goal: {:?}", bad),
-            }
-        }
-        if !agw.invisible_goals.is_empty() {
-            println!("Unsolved metas:");
+    let goals = goals_report(agda).await?;
+    let ok = !goals.failed();
+    report(goals);
+    Ok(ok)
+}
+
+/// Reload, and return the goals instead of reporting them,
+/// for commands that report them along with their own result.
+pub async fn reload_report(agda: &mut Repl) -> Monad<Report> {
+    let da = &mut agda.agda;
+    da.reload_file().await?;
+    goals_report(da).await
+}
+
+/// The goals after loading, or the error.
+async fn goals_report(agda: &mut ReplState) -> Monad<Report> {
+    let agw = match agda.next_all_goals_warnings().await? {
+        Ok(agw) => agw,
+        Err(e) => {
+            clear_line();
+            return Ok(Report::error(e));
         }
-        for meta in agw.invisible_goals {
-            println!("{}", meta);
+    };
+    clear_line();
+    let mut goals = Vec::with_capacity(agw.visible_goals.len());
+    for goal in &agw.visible_goals {
+        // I believe `goal` will always be `OfType`.
+        match goal.try_as_of_type() {
+            Ok(ok) => goals.push(GoalEntry {
+                id: ok.constraint_obj.id,
+                r#type: Some(ok.r#type.clone()),
+                line: None,
+            }),
+            Err(bad) => eprintln!("[WARN]: unexpected goal: {:?}", bad),
         }
-        agda.next_goals().await?;
-        Ok(true)
-    } else {
-        Ok(false)
     }
+    let unsolved_metas = agw.invisible_goals.iter().map(|m| m.to_string()).collect();
+    agda.next_goals().await?;
+    Ok(Report::Goals {
+        goals,
+        unsolved_metas,
+        diagnostics: agw.diagnostics(),
+    })
 }
@@ -0,0 +1,43 @@
+use std::fs::write;
+
+use crate::file_io::{Monad, Repl};
+use crate::output::{report, Report};
+
+/// How many modules to show in the summary.
+const SLOWEST: usize = 5;
+
+pub fn module_graph(agda: &mut Repl, format: Option<&str>, out: Option<&str>) -> Monad {
+    let graph = agda.agda.module_graph_mut();
+    if let Err(e) = graph.scan_sources() {
+        eprintln!("Failed to read the imports of some modules: {}", e);
+    }
+    let text = match format {
+        Some("dot") => graph.to_dot(),
+        Some("json") => graph.to_json()?,
+        Some(other) => {
+            let e = format!("Unknown graph format `{}`, try `dot` or `json`.", other);
+            report(Report::error(e));
+            return Ok(());
+        }
+        None => {
+            let checked = graph.modules.values().filter(|n| n.checked).count();
+            let mut text = format!(
+                "{} modules, {} checked in the last load.\n",
+                graph.modules.len(),
+                checked
+            );
+            for (module, time) in graph.slowest().into_iter().take(SLOWEST) {
+                text.push_str(&format!("{:>8}ms {}\n", time.as_millis(), module));
+            }
+            report(Report::text(text));
+            return Ok(());
+        }
+    };
+    match out {
+        Some(file) => write(file, text),
+        None => {
+            report(Report::text(text));
+            Ok(())
+        }
+    }
+}
@@ -0,0 +1,106 @@
+use std::path::Path;
+
+use agda_mode::cmd::{Cmd, GoalInput};
+use agda_mode::pos::{InteractionId, Interval, NamedMeta};
+use agda_mode::resp::CollectObjs;
+
+use crate::file_io::{Monad, Repl};
+use crate::hint::preprint_with_hint;
+use crate::output::{report, ConstraintEntry, GoalEntry, Report};
+
+/// Where the first interval is: its line if it's in the current file,
+/// or `file:line,col` if it's in another one.
+fn location(agda: &Repl, range: &[Interval]) -> (Option<usize>, Option<String>) {
+    let interval = match range.first() {
+        Some(interval) => interval,
+        None => return (None, None),
+    };
+    match &interval.file {
+        Some(file) if !is_current_file(agda, file) => {
+            let start = interval.start;
+            let location = format!("{}:{},{}", file, start.line, start.col);
+            (None, Some(location))
+        }
+        _ => (Some(interval.start.line), None),
+    }
+}
+
+fn is_current_file(agda: &Repl, file: &str) -> bool {
+    let file = Path::new(file);
+    file == agda.agda.file || file.canonicalize().is_ok_and(|f| f == agda.agda.file)
+}
+
+pub async fn why_in_scope(agda: &mut Repl, i: Option<InteractionId>, name: &str) -> Monad {
+    let command = match i {
+        Some(i) => Cmd::WhyInScope(GoalInput::no_range(i, name.to_owned())),
+        None => Cmd::WhyInScopeToplevel(name.to_owned()),
+    };
+    agda.agda.command(command).await?;
+    if let Some((file, message)) = preprint_with_hint(agda.agda.next_why_in_scope().await?) {
+        report(Report::WhyInScope {
+            name: name.to_owned(),
+            file,
+            message,
+        });
+    }
+    Ok(())
+}
+
+pub async fn constraints(agda: &mut Repl) -> Monad {
+    agda.agda.command(Cmd::Constraints).await?;
+    if let Some(constraints) = preprint_with_hint(agda.agda.next_constraints().await?) {
+        let constraints = constraints
+            .into_iter()
+            .map(|form| {
+                let (line, location) = location(agda, &form.range);
+                ConstraintEntry {
+                    line,
+                    location,
+                    constraint: form.constraint.to_string(),
+                    problems: form.problems,
+                }
+            })
+            .collect();
+        report(Report::Constraints { constraints });
+    }
+    Ok(())
+}
+
+/// All the goals and unsolved metas, with their lines.
+pub async fn metas(agda: &mut Repl) -> Monad {
+    agda.agda.command(Cmd::Metas).await?;
+    let agw = match preprint_with_hint(agda.agda.next_all_goals_warnings().await?) {
+        Some(agw) => agw,
+        None => return Ok(()),
+    };
+    let mut goals = Vec::with_capacity(agw.visible_goals.len());
+    for goal in &agw.visible_goals {
+        match goal.try_as_of_type() {
+            Ok(ok) => goals.push(GoalEntry {
+                id: ok.constraint_obj.id,
+                r#type: Some(ok.r#type.clone()),
+                line: location(agda, &ok.constraint_obj.range).0,
+            }),
+            Err(bad) => eprintln!("[WARN]: unexpected goal: {:?}", bad),
+        }
+    }
+    let metas = agw
+        .invisible_goals
+        .iter()
+        .map(|meta| {
+            let mut range = None;
+            meta.collect_objs(|m: &NamedMeta| {
+                range = range.take().or_else(|| m.range.first().cloned())
+            });
+            let (line, location) = location(agda, range.as_slice());
+            ConstraintEntry {
+                line,
+                location,
+                constraint: meta.to_string(),
+                problems: vec![],
+            }
+        })
+        .collect();
+    report(Report::Metas { goals, metas });
+    Ok(())
+}
@@ -1,13 +1,19 @@
 use crate::file_io::{Monad, Repl};
-use crate::repl::{reload_unit};
+use crate::output::{report, NumberedLine, Report};
+use crate::repl::reload_unit;
+use agda_mode::edit::{line_range, TextEdit};
 use agda_mode::pos::InteractionId;
 
 pub fn show_line(agda: &mut Repl, i: usize) {
     let line_max = agda.line_count();
     if i >= line_max {
-        eprintln!("There are only {} lines in total.", line_max);
+        report(Report::error(format!(
+            "There are only {} lines in total.",
+            line_max
+        )));
     } else {
-        print!("{}", agda.line_in_buffer(i))
+        let text = agda.line_in_buffer(i).to_string();
+        report(Report::Line { line: i, text })
     }
 }
 
@@ -17,29 +23,196 @@ pub async fn pop_line(agda: &mut Repl) -> Monad {
 }
 
 pub async fn push_line(agda: &mut Repl, code: &str) -> Monad {
-    agda.append(code)?;
-    agda.append("\n")?;
+    agda.push_code(code)?;
     reload_unit(agda).await
 }
 
 pub async fn define(agda: &mut Repl, function_name: &&str) -> Monad {
-    agda.append(&format!("{} : ?\n", function_name))?;
-    agda.append(&format!("{} = ?\n", function_name))?;
+    agda.push_code(&format!("{0} : ?\n{0} = ?", function_name))?;
     reload_unit(agda).await
 }
 
 pub async fn intro_pattern(agda: &mut Repl, i: InteractionId, new: &str) -> Monad {
     let ips = agda.agda.interaction_points();
     if i >= ips.len() as i32 || i < 0 {
-        eprintln!("Bad interaction point: {:?}.", i);
+        report(Report::error(format!("Bad interaction point: {:?}.", i)));
         return Ok(());
     }
     let ip = ips[i as usize].clone();
     if agda.intros_in_goal_buffer(ip, new).is_none() {
-        eprintln!("Don't know how to introduce, sorry.");
+        report(Report::error(
+            "Don't know how to introduce, sorry.".to_owned(),
+        ));
         Ok(())
     } else {
+        agda.agda.mark_buffer_changed();
         agda.sync_buffer()?;
         reload_unit(agda).await
     }
 }
+
+pub fn show_lines(agda: &mut Repl, lines: Option<(usize, usize)>) {
+    let source = agda.source();
+    if lines.is_none() && source.is_empty() {
+        return report(Report::Lines { lines: vec![] });
+    }
+    let (first, last) = lines.unwrap_or((1, line_count(&source)));
+    if let Err(e) = check_lines(&source, (first, last)) {
+        return report(Report::error(e));
+    }
+    let goals = agda.agda.interaction_points();
+    let lines = source
+        .lines()
+        .enumerate()
+        .map(|(i, text)| (i + 1, text))
+        .filter(|(line, _)| (first..=last).contains(line))
+        .map(|(line, text)| NumberedLine {
+            line,
+            text: text.to_owned(),
+            goals: goals
+                .iter()
+                .filter(|ip| ip.range.iter().any(|i| i.start.line == line))
+                .map(|ip| ip.id)
+                .collect(),
+        })
+        .collect();
+    report(Report::Lines { lines })
+}
+
+/// Insert `text` as the `n`-th line, `n` can be right after the last line.
+pub async fn insert_line(agda: &mut Repl, n: usize, text: &str) -> Monad {
+    let edit = insert_edit(&agda.source(), n, text);
+    edit_lines(agda, edit).await
+}
+
+pub async fn replace_line(agda: &mut Repl, n: usize, text: &str) -> Monad {
+    let edit = replace_edit(&agda.source(), n, text);
+    edit_lines(agda, edit).await
+}
+
+/// Delete the lines `first` to `last`, the goals in them are gone.
+pub async fn delete_lines(agda: &mut Repl, first: usize, last: usize) -> Monad {
+    let edit = delete_edit(&agda.source(), first, last);
+    edit_lines(agda, edit).await
+}
+
+fn no_line(n: usize) -> String {
+    format!("No line {}.", n)
+}
+
+fn insert_edit(source: &str, n: usize, text: &str) -> Result<TextEdit, String> {
+    if n == line_count(source) + 1 {
+        let at = source.chars().count();
+        let newline = if source.is_empty() || source.ends_with('\n') {
+            ""
+        } else {
+            "\n"
+        };
+        return Ok(TextEdit::insert(at, format!("{}{}\n", newline, text)));
+    }
+    check_lines(source, (n, n))?;
+    let range = line_range(source, n).ok_or_else(|| no_line(n))?;
+    Ok(TextEdit::insert(range.start, format!("{}\n", text)))
+}
+
+fn replace_edit(source: &str, n: usize, text: &str) -> Result<TextEdit, String> {
+    check_lines(source, (n, n))?;
+    let range = line_range(source, n).ok_or_else(|| no_line(n))?;
+    Ok(TextEdit::new(range, text.to_owned()))
+}
+
+fn delete_edit(source: &str, first: usize, last: usize) -> Result<TextEdit, String> {
+    check_lines(source, (first, last))?;
+    let mut start = line_range(source, first)
+        .ok_or_else(|| no_line(first))?
+        .start;
+    let mut end = line_range(source, last).ok_or_else(|| no_line(last))?.end;
+    if end < source.chars().count() {
+        // The line break after the last line.
+        end += 1;
+    } else {
+        // There's no line break after the last line, take the one before.
+        start = start.saturating_sub(1);
+    }
+    Ok(TextEdit::new(start..end, String::new()))
+}
+
+/// The number of lines, not counting the empty one after the last line break.
+fn line_count(source: &str) -> usize {
+    source.lines().count()
+}
+
+/// `(first, last)`, 1-indexed and inclusive, must be in the file.
+fn check_lines(source: &str, (first, last): (usize, usize)) -> Result<(), String> {
+    let count = line_count(source);
+    if first == 0 || last < first || last > count {
+        Err(format!("There are only {} lines in total.", count))
+    } else {
+        Ok(())
+    }
+}
+
+/// Apply the edit (the goals are moved along) and reload.
+async fn edit_lines(agda: &mut Repl, edit: Result<TextEdit, String>) -> Monad {
+    match edit {
+        Ok(edit) => {
+            agda.apply_edits(&[edit]);
+            agda.agda.mark_buffer_changed();
+            agda.sync_buffer()?;
+            reload_unit(agda).await
+        }
+        Err(e) => {
+            report(Report::error(e));
+            Ok(())
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use agda_mode::edit::apply;
+
+    fn insert(source: &str, n: usize) -> Result<String, String> {
+        insert_edit(source, n, "x").map(|edit| apply(source, &[edit]))
+    }
+
+    fn replace(source: &str, n: usize) -> Result<String, String> {
+        replace_edit(source, n, "x").map(|edit| apply(source, &[edit]))
+    }
+
+    fn delete(source: &str, first: usize, last: usize) -> Result<String, String> {
+        delete_edit(source, first, last).map(|edit| apply(source, &[edit]))
+    }
+
+    #[test]
+    fn insert_lines() {
+        assert_eq!(insert("a\nb\n", 1).unwrap(), "x\na\nb\n");
+        assert_eq!(insert("a\nb\n", 2).unwrap(), "a\nx\nb\n");
+        assert_eq!(insert("a\nb\n", 3).unwrap(), "a\nb\nx\n");
+        assert_eq!(insert("a\nb", 3).unwrap(), "a\nb\nx\n");
+        assert_eq!(insert("", 1).unwrap(), "x\n");
+        assert!(insert("a\nb\n", 4).is_err());
+        assert!(insert("a\nb\n", 0).is_err());
+    }
+
+    #[test]
+    fn replace_lines() {
+        assert_eq!(replace("a\nb\n", 2).unwrap(), "a\nx\n");
+        assert_eq!(replace("a\nb", 2).unwrap(), "a\nx");
+        assert!(replace("a\nb\n", 3).is_err());
+        assert!(replace("", 1).is_err());
+    }
+
+    #[test]
+    fn delete_lines() {
+        assert_eq!(delete("a\nb\nc\n", 2, 2).unwrap(), "a\nc\n");
+        assert_eq!(delete("a\nb\nc\n", 1, 2).unwrap(), "c\n");
+        assert_eq!(delete("a\nb\nc\n", 3, 3).unwrap(), "a\nb\n");
+        assert_eq!(delete("a\nb\nc", 2, 3).unwrap(), "a");
+        assert_eq!(delete("a", 1, 1).unwrap(), "");
+        assert!(delete("a\nb\n", 2, 3).is_err());
+        assert!(delete("a\nb\n", 2, 1).is_err());
+        assert!(delete("", 1, 1).is_err());
+    }
+}
@@ -1,22 +1,39 @@
 use agda_mode::agda::ReplState;
 use agda_mode::base::ComputeMode;
-use agda_mode::cmd::Cmd;
+use agda_mode::cmd::{Backend, Cmd};
 use agda_mode::debug::{toggle_debug_command, toggle_debug_response};
 
 use crate::file_io::{Monad, Repl};
 use crate::input::{UserInput, HELP};
 use crate::interact::help;
+use crate::output::{self, report, GoalEntry, Report};
 
+pub use self::compile::*;
 pub use self::goal::*;
 pub use self::goal_list::*;
+pub use self::graph::*;
+pub use self::inspect::*;
 pub use self::line::*;
+pub use self::refine::*;
+pub use self::search::*;
+pub use self::solve::*;
 
+mod compile;
 mod goal;
 mod goal_list;
+mod graph;
+mod inspect;
 mod line;
+mod refine;
+mod search;
+mod solve;
 
 pub async fn line(agda: &mut Repl, line: &str) -> Monad<bool> {
-    line_impl(agda, UserInput::from(line)).await
+    output::begin_command();
+    let line = agda.config.aliases.expand(line);
+    let exit = line_impl(agda, UserInput::from(line.as_str())).await?;
+    output::end_command();
+    Ok(exit)
 }
 
 async fn line_impl<'a>(agda: &mut Repl, line: UserInput<'a>) -> Monad<bool> {
@@ -25,40 +42,76 @@ async fn line_impl<'a>(agda: &mut Repl, line: UserInput<'a>) -> Monad<bool> {
         Define(function_name) => define(agda, &function_name).await?,
         PushLine(code) => push_line(agda, code).await?,
         PopLine => pop_line(agda).await?,
-        DumpProof => agda.dump_proof()?,
+        DumpProof => agda.dump_proof(),
         ShowLine(i) => show_line(agda, i),
+        ShowLines(lines) => show_lines(agda, lines),
+        InsertLine(n, text) => insert_line(agda, n, text).await?,
+        ReplaceLine(n, text) => replace_line(agda, n, text).await?,
+        DeleteLines(first, last) => delete_lines(agda, first, last).await?,
         Give(i, new) => give(agda, i, new).await?,
         Infer(i, new) => infer(agda, i, new).await?,
-        Simplify(i, new) => norm(agda, i, new, ComputeMode::DefaultCompute).await?,
+        Simplify(i, new) => norm(agda, i, new, agda.config.compute).await?,
         Normalize(i, new) => norm(agda, i, new, ComputeMode::UseShowInstance).await?,
         IntroPattern(i, var) => intro_pattern(agda, i, var).await?,
         Type(i) => ty(agda, i).await?,
         Context(i) => ctx(agda, i).await?,
         Split(i, pat) => split(agda, i, pat).await?,
+        Refine(i, expr) => refine(agda, i, expr).await?,
+        Intro(i) => intro(agda, i).await?,
+        Extract(i, name, args) => extract(agda, i, name, args).await?,
+        Auto(i, hints) => auto(agda, i, hints).await?,
+        AutoAll => auto_all(agda).await?,
+        Solve(i) => solve(agda, i).await?,
+        SolveAll => solve_all(agda).await?,
         Reload => {
             reload(agda).await?;
         }
         ReadToEnd => loop {
             agda.agda.response().await?;
         },
-        SearchModule(s) => {
-            let command = Cmd::search_module(s.to_owned());
-            agda.agda.command(command).await?;
-            let e: String = agda.agda.next_error().await?.into();
-            eprintln!("Error:");
-            eprintln!("{}", e);
+        SearchAbout(s) => search_about(agda, s).await?,
+        ModuleContents(i, module) => module_contents(agda, i, module).await?,
+        WhyInScope(i, name) => why_in_scope(agda, i, name).await?,
+        Constraints => constraints(agda).await?,
+        Compile(args) => {
+            let mut args = args.split_whitespace();
+            let backend = match args.next() {
+                Some(name) => name.parse(),
+                None => Ok(Backend::GHC(Default::default())),
+            };
+            match backend {
+                Ok(backend) => compile(agda, backend, args.map(str::to_owned).collect()).await?,
+                Err(e) => report(Report::error(e)),
+            }
+        }
+        Metas => metas(agda).await?,
+        ListGoals => {
+            let goals = agda
+                .agda
+                .goals()
+                .into_iter()
+                .map(|goal| GoalEntry {
+                    id: goal.id(),
+                    line: Some(goal.interaction_point.the_interval().start.line),
+                    r#type: goal.r#type,
+                })
+                .collect();
+            let stale = agda.agda.buffer_changed();
+            report(Report::GoalList { goals, stale });
         }
-        ListGoals => agda.agda.print_goal_list(),
+        ModuleGraph(format, out) => module_graph(agda, format, out)?,
         Help => {
-            println!("{}", help(agda.is_plain));
+            let mut text = format!("{}\n", help(agda.is_plain));
             for line in HELP {
-                println!("{}", line);
+                text.push_str(line);
+                text.push('\n');
             }
+            report(Report::text(text));
         }
         ToggleDebugCommand => toggle_debug_command(),
         ToggleDebugResponse => toggle_debug_response(),
-        Unknown(Some(err)) => println!("Wait, {}", err),
-        Unknown(None) => println!("Sorry, I don't understand."),
+        Unknown(Some(err)) => report(Report::error(format!("Wait, {}", err))),
+        Unknown(None) => report(Report::error("Sorry, I don't understand.".to_owned())),
         Exit => {
             finish(&mut agda.agda).await?;
             return Ok(true);
@@ -67,7 +120,7 @@ async fn line_impl<'a>(agda: &mut Repl, line: UserInput<'a>) -> Monad<bool> {
     Ok(false)
 }
 
-async fn finish(agda: &mut ReplState) -> Monad {
+pub async fn finish(agda: &mut ReplState) -> Monad {
     agda.command(Cmd::Abort).await?;
     agda.shutdown().await
 }
@@ -0,0 +1,144 @@
+use agda_mode::cmd::{Cmd, GoalInput};
+use agda_mode::edit;
+use agda_mode::pos::{InteractionId, InteractionPoint};
+use agda_mode::resp::{DisplayInfo, GoalInfo, Resp};
+
+use crate::file_io::{Monad, Repl};
+use crate::hint::preprint_with_hint;
+use crate::output::{report, Report};
+use crate::repl::reload_report;
+
+/// Refine with `expr`, or introduce something if it's empty (like Emacs' `C-c C-r`).
+pub async fn refine(agda: &mut Repl, i: InteractionId, expr: &str) -> Monad {
+    let command = Cmd::RefineOrIntro {
+        dunno: false,
+        input: GoalInput::no_range(i, expr.to_owned()),
+    };
+    refine_with(agda, i, command, expr).await
+}
+
+pub async fn intro(agda: &mut Repl, i: InteractionId) -> Monad {
+    let command = Cmd::Intro {
+        dunno: false,
+        input: GoalInput::simple(i),
+    };
+    refine_with(agda, i, command, "").await
+}
+
+/// Ask Agda for the type of a helper function `name args`,
+/// define it above the current definition, and give `name args` to the goal.
+pub async fn extract(agda: &mut Repl, i: InteractionId, name: &str, args: &str) -> Monad {
+    let call = format!("{} {}", name, args);
+    let call = call.trim();
+    let ip = match edit::find_goal(agda.agda.interaction_points(), i) {
+        Some(ip) => ip.clone(),
+        None => {
+            report(Report::error(format!("Bad interaction point: {:?}.", i)));
+            return Ok(());
+        }
+    };
+    let command = Cmd::HelperFunction(GoalInput::no_range(i, call.to_owned()).into());
+    agda.agda.command(command).await?;
+    let gs = match preprint_with_hint(agda.agda.next_goal_specific().await?) {
+        Some(gs) => gs,
+        None => return Ok(()),
+    };
+    let signature = match gs.goal_info {
+        GoalInfo::HelperFunction { signature } => signature,
+        _ => unreachable!(),
+    };
+    match edit::helper_function(&agda.source(), &ip, &signature) {
+        Ok(edits) => agda.apply_edits(&edits),
+        Err(e) => {
+            report(Report::error(e));
+            return Ok(());
+        }
+    }
+    agda.agda.mark_buffer_changed();
+    agda.sync_buffer()?;
+    // Agda knows `name` only after a reload, which renumbers the goals,
+    // so the goal is found by its (shifted) position.
+    let start = |ip: &InteractionPoint| ip.range.first().map(|r| r.start.pos);
+    let goal_start = edit::find_goal(agda.agda.interaction_points(), i).and_then(start);
+    let mut reload = reload_report(agda).await?;
+    if reload.failed() {
+        let reload = Some(Box::new(reload));
+        report(Report::Edited { goal: i, reload });
+        return Ok(());
+    }
+    let ips = agda.agda.interaction_points();
+    let new_id = match ips
+        .iter()
+        .find(|ip| goal_start.is_some() && start(ip) == goal_start)
+    {
+        Some(ip) => ip.id,
+        None => {
+            report(Report::error(format!(
+                "Cannot find the goal after adding `{}`.",
+                name
+            )));
+            return Ok(());
+        }
+    };
+    let command = Cmd::give(GoalInput::no_range(new_id, call.to_owned()));
+    agda.agda.command(command).await?;
+    let action = match preprint_with_hint(agda.agda.next_give_action().await?) {
+        Some(action) => action,
+        None => return Ok(()),
+    };
+    match edit::give(&action, call) {
+        Ok(edits) => agda.apply_edits(&edits),
+        Err(e) => {
+            report(Report::error(e));
+            return Ok(());
+        }
+    }
+    agda.sync_buffer()?;
+    agda.agda.next_goals().await?;
+    // The goals of the reload, but the given one.
+    if let Report::Goals { goals, .. } = &mut reload {
+        goals.retain(|g| g.id != new_id);
+    }
+    let reload = Some(Box::new(reload));
+    report(Report::Edited { goal: i, reload });
+    Ok(())
+}
+
+async fn refine_with(agda: &mut Repl, i: InteractionId, command: Cmd, expr: &str) -> Monad {
+    agda.agda.command(command).await?;
+    for resp in agda.agda.finish_command().await? {
+        let info = match resp {
+            Resp::GiveAction(action) => {
+                match edit::give(&action, expr) {
+                    Ok(edits) => agda.apply_edits(&edits),
+                    Err(e) => {
+                        report(Report::error(e));
+                        return Ok(());
+                    }
+                }
+                // The `?`s in the refined expression are the new goals,
+                // Agda only knows them after a reload.
+                agda.agda.mark_buffer_changed();
+                agda.sync_buffer()?;
+                let reload = Some(Box::new(reload_report(agda).await?));
+                report(Report::Edited { goal: i, reload });
+                return Ok(());
+            }
+            Resp::DisplayInfo { info: Some(info) } => info,
+            _ => continue,
+        };
+        match info {
+            DisplayInfo::Error { error } => report(Report::error(error.into())),
+            DisplayInfo::IntroNotFound => report(Report::error(
+                "There's nothing to introduce here.".to_owned(),
+            )),
+            DisplayInfo::IntroConstructorUnknown { constructors } => report(Report::Constructors {
+                goal: i,
+                constructors,
+            }),
+            _ => continue,
+        }
+        return Ok(());
+    }
+    Ok(())
+}
@@ -0,0 +1,46 @@
+use agda_mode::cmd::{Cmd, GoalInput};
+use agda_mode::pos::InteractionId;
+use agda_mode::resp::NamedPrettyTCM;
+
+use crate::file_io::{Monad, Repl};
+use crate::hint::preprint_with_hint;
+use crate::output::{report, NamedEntry, Report};
+
+fn entries(named: Vec<NamedPrettyTCM>) -> Vec<NamedEntry> {
+    let entry = |n: NamedPrettyTCM| NamedEntry {
+        name: n.name,
+        term: n.term,
+    };
+    named.into_iter().map(entry).collect()
+}
+
+pub async fn search_about(agda: &mut Repl, search: &str) -> Monad {
+    agda.agda
+        .command(Cmd::search_about(search.to_owned()))
+        .await?;
+    if let Some(results) = preprint_with_hint(agda.agda.next_search_about().await?) {
+        report(Report::SearchAbout {
+            search: search.to_owned(),
+            results: entries(results),
+        });
+    }
+    Ok(())
+}
+
+/// Contents of `module`, in the scope of the goal `i` or at the top-level.
+pub async fn module_contents(agda: &mut Repl, i: Option<InteractionId>, module: &str) -> Monad {
+    let command = match i {
+        Some(i) => Cmd::module_contents(GoalInput::no_range(i, module.to_owned())),
+        None => Cmd::search_module(module.to_owned()),
+    };
+    agda.agda.command(command).await?;
+    if let Some(contents) = preprint_with_hint(agda.agda.next_module_contents().await?) {
+        report(Report::ModuleContents {
+            module: module.to_owned(),
+            telescope: contents.telescope.iter().map(|t| t.to_string()).collect(),
+            modules: contents.names,
+            contents: entries(contents.contents),
+        });
+    }
+    Ok(())
+}
@@ -0,0 +1,94 @@
+use agda_mode::cmd::{Cmd, GoalInput};
+use agda_mode::edit;
+use agda_mode::pos::InteractionId;
+use agda_mode::resp::{DisplayInfo, Resp};
+
+use crate::file_io::{Monad, Repl};
+use crate::output::{report, Report};
+use crate::repl::reload_report;
+
+pub async fn auto(agda: &mut Repl, i: InteractionId, hints: &str) -> Monad {
+    let command = Cmd::AutoOne(GoalInput::no_range(i, hints.to_owned()));
+    run_solver(agda, command, vec![i]).await
+}
+
+pub async fn auto_all(agda: &mut Repl) -> Monad {
+    let goals = goal_ids(agda);
+    run_solver(agda, Cmd::AutoAll, goals).await
+}
+
+pub async fn solve(agda: &mut Repl, i: InteractionId) -> Monad {
+    run_solver(agda, Cmd::solve_one(GoalInput::simple(i)), vec![i]).await
+}
+
+pub async fn solve_all(agda: &mut Repl) -> Monad {
+    let goals = goal_ids(agda);
+    run_solver(agda, Cmd::SolveAll(Default::default()), goals).await
+}
+
+fn goal_ids(agda: &Repl) -> Vec<InteractionId> {
+    agda.agda
+        .interaction_points()
+        .iter()
+        .map(|ip| ip.id)
+        .collect()
+}
+
+/// Run a proof search command, fill the solved goals among `goals`.
+async fn run_solver(agda: &mut Repl, command: Cmd, goals: Vec<InteractionId>) -> Monad {
+    agda.agda.command(command).await?;
+    let mut edits = Vec::new();
+    let mut solved = Vec::new();
+    let mut messages = Vec::new();
+    // Unlike `auto`, `solve` doesn't fill the goals on Agda's side.
+    let mut needs_reload = false;
+    for resp in agda.agda.finish_command().await? {
+        let result = match resp {
+            Resp::GiveAction(action) => {
+                solved.push(action.interaction_point.id);
+                edit::give(&action, "")
+            }
+            Resp::SolveAll { solutions } => {
+                needs_reload |= !solutions.is_empty();
+                solved.extend(solutions.iter().map(|s| s.interaction_point.id));
+                edit::solve_all(&solutions)
+            }
+            Resp::DisplayInfo {
+                info: Some(DisplayInfo::Auto { info }),
+            } => {
+                messages.push(info);
+                continue;
+            }
+            Resp::DisplayInfo {
+                info: Some(DisplayInfo::Error { error }),
+            } => {
+                report(Report::error(error.into()));
+                return Ok(());
+            }
+            _ => continue,
+        };
+        match result {
+            Ok(new_edits) => edits.extend(new_edits),
+            Err(e) => messages.push(e),
+        }
+    }
+    if !edits.is_empty() {
+        agda.apply_edits(&edits);
+        agda.sync_buffer()?;
+    }
+    let unsolved = goals.into_iter().filter(|i| !solved.contains(i)).collect();
+    let message = Some(messages.join("\n")).filter(|m| !m.trim().is_empty());
+    let reload = if needs_reload {
+        agda.agda.mark_buffer_changed();
+        Some(Box::new(reload_report(agda).await?))
+    } else {
+        None
+    };
+    report(Report::Solved {
+        solved,
+        unsolved,
+        message,
+        reload,
+    });
+    Ok(())
+}
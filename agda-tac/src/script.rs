@@ -0,0 +1,120 @@
+use std::fs::{read_to_string, write};
+use std::io::{self, Read};
+use std::path::Path;
+
+use crate::config::Aliases;
+use crate::file_io::{Monad, Repl};
+use crate::input::UserInput;
+use crate::output::{command_failed, is_json};
+use crate::repl::{finish, line, poll_goals, reload};
+
+/// Exit codes of [`run`].
+pub const SUCCESS: i32 = 0;
+pub const AGDA_ERROR: i32 = 1;
+pub const SCRIPT_ERROR: i32 = 2;
+pub const UNSOLVED_GOALS: i32 = 3;
+
+fn read_script(script: &Path) -> Monad<String> {
+    if script == Path::new("-") {
+        let mut s = String::new();
+        io::stdin().read_to_string(&mut s)?;
+        Ok(s)
+    } else {
+        read_to_string(script)
+    }
+}
+
+/// Lines that are neither blank nor comments (`--` or `#`), with line numbers.
+fn commands(script: &str) -> impl Iterator<Item = (usize, &str)> {
+    script
+        .lines()
+        .enumerate()
+        .map(|(i, l)| (i + 1, l.trim()))
+        .filter(|(_, l)| !l.is_empty() && !l.starts_with("--") && !l.starts_with('#'))
+}
+
+/// Why `command` isn't a command, after expanding the aliases.
+fn invalid(aliases: &Aliases, command: &str) -> Option<String> {
+    match UserInput::from(aliases.expand(command).as_str()) {
+        UserInput::Unknown(err) => Some(err.unwrap_or("unknown command").to_owned()),
+        _ => None,
+    }
+}
+
+/// Run the commands in `script`, stop on the first error,
+/// and return the exit code.
+pub async fn run(mut agda: Repl, script: &Path, proof: Option<&Path>) -> Monad<i32> {
+    let script = match read_script(script) {
+        Ok(script) => script,
+        Err(e) => {
+            eprintln!("Failed to read {}: {}", script.display(), e);
+            return Ok(SCRIPT_ERROR);
+        }
+    };
+    poll_goals(&mut agda.agda).await?;
+    if agda.agda.last_error().is_some() {
+        eprintln!("The file doesn't type-check.");
+        finish(&mut agda.agda).await?;
+        return Ok(AGDA_ERROR);
+    }
+    for (line_num, command) in commands(&script) {
+        if !is_json() {
+            println!("> {}", command);
+        }
+        if let Some(err) = invalid(&agda.config.aliases, command) {
+            eprintln!("Script error at line {}: {}", line_num, err);
+            finish(&mut agda.agda).await?;
+            return Ok(SCRIPT_ERROR);
+        }
+        // The end of the script, the proof is still checked and written.
+        let expanded = agda.config.aliases.expand(command);
+        if matches!(UserInput::from(expanded.as_str()), UserInput::Exit) {
+            break;
+        }
+        line(&mut agda, command).await?;
+        if agda.agda.last_error().is_some() {
+            eprintln!("Agda error at line {}: {}", line_num, command);
+            finish(&mut agda.agda).await?;
+            return Ok(AGDA_ERROR);
+        }
+        // Like a goal that doesn't exist, or a line out of range.
+        if command_failed() {
+            eprintln!("Script error at line {}: {}", line_num, command);
+            finish(&mut agda.agda).await?;
+            return Ok(SCRIPT_ERROR);
+        }
+    }
+    if agda.agda.buffer_changed() {
+        reload(&mut agda).await?;
+        if agda.agda.last_error().is_some() {
+            eprintln!("The final proof doesn't type-check.");
+            finish(&mut agda.agda).await?;
+            return Ok(AGDA_ERROR);
+        }
+    }
+    if let Some(proof) = proof {
+        write(proof, agda.source())?;
+    }
+    let code = if agda.agda.goals().is_empty() {
+        SUCCESS
+    } else {
+        eprintln!("There are unsolved goals.");
+        UNSOLVED_GOALS
+    };
+    finish(&mut agda.agda).await?;
+    Ok(code)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn aliases() {
+        let aliases: Aliases = toml::from_str("g = \"give\"\nall = \"solve-all\"").unwrap();
+        assert_eq!(invalid(&aliases, "g 0 refl"), None);
+        assert_eq!(invalid(&aliases, "all"), None);
+        assert!(invalid(&aliases, "x 0").is_some());
+        assert!(invalid(&Aliases::default(), "g 0 refl").is_some());
+    }
+}
@@ -8,13 +8,19 @@ use tokio::process::{Child, ChildStdin, ChildStdout, Command};
 use crate::cmd::{Cmd, IOTCM};
 use crate::debug::debug_command;
 
+pub use self::progress::*;
 pub use self::read::*;
 pub use self::repl::*;
+pub use self::state::*;
 
+/// Loading progress callbacks.
+mod progress;
 /// Agda message reading.
 mod read;
 /// Repl state wrapper.
 mod repl;
+/// Session information collected from the responses.
+mod state;
 /// Verify whether Agda is working.
 pub mod verify;
 
@@ -26,8 +32,18 @@ pub struct ProcessStdio(pub Child, pub JustStdio);
 pub struct JustStdio(pub ChildStdin, pub ChildStdout);
 
 pub fn init_agda_process(agda_program: &Path) -> io::Result<ProcessStdio> {
+    init_agda_process_with_args(agda_program, &[])
+}
+
+/// Like [`init_agda_process`], with extra command-line arguments,
+/// like `+RTS -M4G -RTS`.
+pub fn init_agda_process_with_args(
+    agda_program: &Path,
+    args: &[String],
+) -> io::Result<ProcessStdio> {
     let mut process = Command::new(agda_program)
         .arg(INTERACTION_COMMAND)
+        .args(args)
         .stdout(Stdio::piped())
         .stdin(Stdio::piped())
         .spawn()?; // cannot spawn
@@ -39,24 +55,65 @@ pub fn init_agda_process(agda_program: &Path) -> io::Result<ProcessStdio> {
 
 impl ReplState {
     pub async fn start(agda_program: &Path, file: PathBuf) -> io::Result<Self> {
-        let JustStdio(stdin, out) = start_agda(agda_program);
-        Self::from_io(stdin, BufReader::new(out), file).await
+        Self::start_with_flags(agda_program, file, vec![]).await
+    }
+
+    /// Like [`start`](Self::start), but the file is (re)loaded with `load_flags`,
+    /// see [`AgdaOptions`](crate::cmd::AgdaOptions).
+    pub async fn start_with_flags(
+        agda_program: &Path,
+        file: PathBuf,
+        load_flags: Vec<String>,
+    ) -> io::Result<Self> {
+        Self::start_with(agda_program, &[], file, load_flags).await
+    }
+
+    /// Like [`start_with_flags`](Self::start_with_flags),
+    /// but Agda is started with `agda_args`, like `+RTS -M4G -RTS`.
+    pub async fn start_with(
+        agda_program: &Path,
+        agda_args: &[String],
+        file: PathBuf,
+        load_flags: Vec<String>,
+    ) -> io::Result<Self> {
+        let JustStdio(stdin, out) = start_agda_with_args(agda_program, agda_args);
+        Self::from_io_with_flags(stdin, BufReader::new(out), file, load_flags).await
     }
 
     pub async fn from_io(
+        stdin: ChildStdin,
+        stdout: BufReader<ChildStdout>,
+        file: PathBuf,
+    ) -> io::Result<Self> {
+        Self::from_io_with_flags(stdin, stdout, file, vec![]).await
+    }
+
+    pub async fn from_io_with_flags(
         mut stdin: ChildStdin,
         stdout: BufReader<ChildStdout>,
         file: PathBuf,
+        load_flags: Vec<String>,
     ) -> io::Result<Self> {
-        let iotcm = load_file(file.clone());
+        let command = Cmd::Load {
+            path: file.clone(),
+            flags: load_flags.clone(),
+        };
+        let iotcm = IOTCM::simple(file.clone(), command);
         send_command(&mut stdin, &iotcm).await?;
-        Ok(Self {
+        let mut state = Self {
             file,
+            load_flags,
             iotcm,
             stdin,
             interaction_points: vec![],
+            error_jump: None,
+            progress: None,
+            module_graph: Default::default(),
+            session: Default::default(),
             agda: AgdaRead::from(stdout),
-        })
+        };
+        state.begin_load();
+        Ok(state)
     }
 }
 
@@ -64,10 +121,16 @@ impl ReplState {
 ///
 /// Note that this function may panic.
 pub fn start_agda(agda_program: &Path) -> JustStdio {
-    let ProcessStdio(process, stdio) = init_agda_process(agda_program).expect(START_FAIL);
+    start_agda_with_args(agda_program, &[])
+}
+
+/// Like [`start_agda`], with extra command-line arguments.
+pub fn start_agda_with_args(agda_program: &Path, args: &[String]) -> JustStdio {
+    let ProcessStdio(process, stdio) =
+        init_agda_process_with_args(agda_program, args).expect(START_FAIL);
     tokio::spawn(async {
         let status = process.wait_with_output().await.expect(START_FAIL);
-        println!("Agda exits with status {}.", status.status);
+        eprintln!("Agda exits with status {}.", status.status);
     });
     stdio
 }
@@ -76,7 +139,7 @@ pub fn start_agda(agda_program: &Path) -> JustStdio {
 pub async fn send_command(stdin: &mut ChildStdin, command: &IOTCM) -> io::Result<()> {
     let string = command.to_string();
     debug_command(format!("[CMD]: {}", string));
-    stdin.write(string.as_bytes()).await?;
+    stdin.write_all(string.as_bytes()).await?;
     stdin.flush().await
 }
 
@@ -0,0 +1,48 @@
+use std::fmt::{Debug, Error, Formatter};
+
+use crate::agda::ReplState;
+use crate::resp::Progress;
+
+pub type ProgressCallback = Box<dyn FnMut(&Progress) + Send>;
+
+/// A per-session progress callback.
+pub struct ProgressListener {
+    /// Messages with a higher debug level are not reported.
+    pub max_debug_level: i32,
+    callback: ProgressCallback,
+}
+
+impl Debug for ProgressListener {
+    fn fmt(&self, f: &mut Formatter) -> Result<(), Error> {
+        f.debug_struct("ProgressListener")
+            .field("max_debug_level", &self.max_debug_level)
+            .finish()
+    }
+}
+
+impl ReplState {
+    /// Report the loading progress to `callback`.
+    /// [`Progress::Clear`](crate::resp::Progress::Clear) is always reported.
+    pub fn on_progress(
+        &mut self,
+        max_debug_level: i32,
+        callback: impl FnMut(&Progress) + Send + 'static,
+    ) {
+        self.progress = Some(ProgressListener {
+            max_debug_level,
+            callback: Box::new(callback),
+        });
+    }
+
+    pub fn stop_progress(&mut self) {
+        self.progress = None;
+    }
+
+    pub(super) fn report_progress(&mut self, level: i32, progress: &Progress) {
+        if let Some(listener) = &mut self.progress {
+            if level <= listener.max_debug_level || progress == &Progress::Clear {
+                (listener.callback)(progress)
+            }
+        }
+    }
+}
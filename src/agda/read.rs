@@ -49,6 +49,8 @@ impl AgdaRead {
 impl ReplState {
     /// Await the next Agda response.
     pub async fn response(&mut self) -> io::Result<Resp> {
-        self.agda.response().await
+        let resp = self.agda.response().await?;
+        self.observe(&resp);
+        Ok(resp)
     }
 }
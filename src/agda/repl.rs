@@ -5,10 +5,12 @@ use tokio::io::AsyncWriteExt;
 use tokio::process::ChildStdin;
 
 use crate::cmd::{Cmd, IOTCM};
+use crate::diag::ErrorJump;
+use crate::graph::ModuleGraph;
 use crate::pos::InteractionPoint;
-use crate::resp::{AgdaError, DisplayInfo, Resp};
+use crate::resp::{AgdaError, DisplayInfo, NamedPrettyTCM, OutputForm, Resp};
 
-use super::{send_command, AgdaRead};
+use super::{send_command, AgdaRead, ProgressListener, Session};
 
 /// Simple REPL state wrapper.
 #[derive(Debug)]
@@ -16,7 +18,13 @@ pub struct ReplState {
     pub stdin: ChildStdin,
     pub agda: AgdaRead,
     pub file: PathBuf,
+    /// The flags of [`reload_file`](Self::reload_file).
+    pub load_flags: Vec<String>,
     pub(super) interaction_points: Vec<InteractionPoint>,
+    pub(super) error_jump: Option<ErrorJump>,
+    pub(super) progress: Option<ProgressListener>,
+    pub(super) module_graph: ModuleGraph,
+    pub(super) session: Session,
     pub(super) iotcm: IOTCM,
 }
 
@@ -32,30 +40,40 @@ pub fn preprint_agda_result<T>(t: AgdaResult<T>) -> Option<T> {
 impl ReplState {
     /// Print all goals.
     pub fn print_goal_list(&self) {
-        let ips = self.interaction_points();
-        if ips.is_empty() {
+        let goals = self.goals();
+        if goals.is_empty() {
             println!("No goals, you're all set.");
         }
-        for interaction_point in ips {
+        for goal in goals {
             // This shouldn't fail
-            let range = &interaction_point.range;
+            let range = &goal.interaction_point.range;
             debug_assert_eq!(range.len(), 1);
             let interval = &range[0];
-            println!("?{} at line {}", interaction_point.id, interval.start.line)
+            match &goal.r#type {
+                Some(ty) => println!("?{} : {} at line {}", goal.id(), ty, interval.start.line),
+                None => println!("?{} at line {}", goal.id(), interval.start.line),
+            }
         }
     }
 
     pub async fn reload_file(&mut self) -> io::Result<()> {
-        self.command(Cmd::load_simple(self.file.clone())).await
+        let path = self.file.clone();
+        let flags = self.load_flags.clone();
+        self.command(Cmd::Load { path, flags }).await
     }
 
     pub async fn command(&mut self, cmd: Cmd) -> io::Result<()> {
+        self.error_jump = None;
+        self.begin_command();
+        if let Cmd::Load { .. } | Cmd::Compile { .. } = cmd {
+            self.begin_load();
+        }
         self.iotcm.command = cmd;
         send_command(&mut self.stdin, &self.iotcm).await
     }
 
     pub async fn command_raw(&mut self, raw_command: &str) -> io::Result<()> {
-        self.stdin.write(raw_command.as_bytes()).await?;
+        self.stdin.write_all(raw_command.as_bytes()).await?;
         self.stdin.flush().await
     }
 
@@ -66,13 +84,40 @@ impl ReplState {
     /// Skip information until the next display info.
     pub async fn next_display_info(&mut self) -> io::Result<DisplayInfo> {
         loop {
-            match self.response().await? {
-                Resp::DisplayInfo { info: Some(info) } => break Ok(info),
-                _ => {}
+            if let Resp::DisplayInfo { info: Some(info) } = self.response().await? {
+                break Ok(info);
             }
         }
     }
 
+    /// Keep track of the responses that describe the session state,
+    /// called on every response.
+    pub(super) fn observe(&mut self, resp: &Resp) {
+        if let Some(jump) = resp.error_jump() {
+            self.error_jump = Some(jump);
+        }
+        if let Some((level, progress)) = resp.progress() {
+            self.module_graph.record(&progress);
+            self.report_progress(level, &progress);
+        }
+        self.observe_session(resp);
+    }
+
+    /// Where Agda asked to jump to after the last command failed.
+    /// This is sent after the error itself.
+    pub fn error_jump(&self) -> Option<&ErrorJump> {
+        self.error_jump.as_ref()
+    }
+
+    /// The modules loaded so far, see [`ModuleGraph`](crate::graph::ModuleGraph).
+    pub fn module_graph(&self) -> &ModuleGraph {
+        &self.module_graph
+    }
+
+    pub fn module_graph_mut(&mut self) -> &mut ModuleGraph {
+        &mut self.module_graph
+    }
+
     /// Returns the latest [`next_goals`](Self::next_goals) result.
     pub fn interaction_points(&self) -> &[InteractionPoint] {
         &self.interaction_points
@@ -88,25 +133,87 @@ impl ReplState {
     /// you've already eliminated errors.
     /// Therefore this method don't deal with errors.
     pub async fn next_goals(&mut self) -> io::Result<()> {
-        use Resp::*;
-        self.interaction_points = loop {
+        // The interaction points are stored by `observe`.
+        while !matches!(self.response().await?, Resp::InteractionPoints { .. }) {}
+        Ok(())
+    }
+
+    /// Collect the responses of the last command, until Agda is ready for the next one.
+    /// This is for the commands without a final response, like [`AutoAll`](Cmd::AutoAll):
+    /// it sends [`ShowVersion`](Cmd::ShowVersion) and waits for its answer.
+    pub async fn finish_command(&mut self) -> io::Result<Vec<Resp>> {
+        let mut iotcm = self.iotcm.clone();
+        iotcm.command = Cmd::ShowVersion;
+        send_command(&mut self.stdin, &iotcm).await?;
+        let mut responses = Vec::new();
+        loop {
             match self.response().await? {
-                InteractionPoints { interaction_points } => break interaction_points,
+                Resp::DisplayInfo {
+                    info: Some(DisplayInfo::Version { .. }),
+                } => break Ok(responses),
+                resp => responses.push(resp),
+            }
+        }
+    }
+
+    /// Skip information until the next search result.
+    pub async fn next_search_about(&mut self) -> NextResult<Vec<NamedPrettyTCM>> {
+        loop {
+            match self.next_display_info().await? {
+                DisplayInfo::Error { error } => break Ok(error.into()),
+                DisplayInfo::SearchAbout { results, .. } => break Ok(Ok(results)),
                 _ => {}
             }
-        };
-        Ok(())
+        }
     }
 
-    /// Skip information until an error.
-    pub async fn next_error(&mut self) -> io::Result<AgdaError> {
+    /// Skip information until the next constraint list.
+    pub async fn next_constraints(&mut self) -> NextResult<Vec<OutputForm>> {
         loop {
             match self.next_display_info().await? {
-                DisplayInfo::Error { error } => break Ok(error),
+                DisplayInfo::Error { error } => break Ok(error.into()),
+                DisplayInfo::Constraints { constraints } => break Ok(Ok(constraints)),
                 _ => {}
             }
         }
     }
+
+    /// Skip information until the compilation finishes,
+    /// which is the warnings and the errors of the backend.
+    pub async fn next_compilation_ok(&mut self) -> NextResult<(String, String)> {
+        loop {
+            match self.next_display_info().await? {
+                DisplayInfo::Error { error } => break Ok(error.into()),
+                DisplayInfo::CompilationOk { warnings, errors } => {
+                    break Ok(Ok((warnings, errors)))
+                }
+                _ => {}
+            }
+        }
+    }
+
+    /// Skip information until the next scope explanation,
+    /// which is the file where the name is defined and the explanation.
+    pub async fn next_why_in_scope(&mut self) -> NextResult<(String, String)> {
+        loop {
+            match self.next_display_info().await? {
+                DisplayInfo::Error { error } => break Ok(error.into()),
+                DisplayInfo::WhyInScope {
+                    filepath, message, ..
+                } => break Ok(Ok((filepath, message))),
+                _ => {}
+            }
+        }
+    }
+
+    /// Skip information until an error.
+    pub async fn next_error(&mut self) -> io::Result<AgdaError> {
+        loop {
+            if let DisplayInfo::Error { error } = self.next_display_info().await? {
+                break Ok(error);
+            }
+        }
+    }
 }
 
 macro_rules! next_resp_of {
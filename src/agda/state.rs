@@ -0,0 +1,295 @@
+use std::collections::BTreeMap;
+
+use crate::agda::ReplState;
+use crate::diag::ErrorJump;
+use crate::edit::{retain_goals, shift_interaction_points, TextEdit};
+use crate::pos::{InteractionId, InteractionPoint};
+use crate::resp::{AspectHighlight, CommandState, DisplayInfo, Resp, Status};
+
+/// A goal, merged from [`Resp::InteractionPoints`](crate::resp::Resp::InteractionPoints)
+/// and [`AllGoalsWarnings`](crate::resp::AllGoalsWarnings).
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct Goal {
+    pub interaction_point: InteractionPoint,
+    /// The type in the latest `AllGoalsWarnings`.
+    pub r#type: Option<String>,
+}
+
+impl Goal {
+    pub fn id(&self) -> InteractionId {
+        self.interaction_point.id
+    }
+}
+
+/// The error of the last command, kept while sending commands of our own,
+/// see [`ReplState::save_error`].
+#[derive(Debug, Clone)]
+pub struct SavedError {
+    error: Option<String>,
+    jump: Option<ErrorJump>,
+}
+
+/// What the responses tell about the session, besides the goals.
+#[derive(Debug, Clone, Default)]
+pub(super) struct Session {
+    status: Option<Status>,
+    current_file: Option<String>,
+    goal_types: BTreeMap<InteractionId, String>,
+    /// Is there a load command without a result yet?
+    loading: bool,
+    buffer_changed: bool,
+    /// Are the goals moved by edits since the last load?
+    goals_shifted: bool,
+    last_error: Option<String>,
+    /// How many loads have finished.
+    loads: usize,
+    highlighting: Vec<AspectHighlight>,
+}
+
+impl Session {
+    fn begin_load(&mut self) {
+        self.loading = true;
+        self.goals_shifted = false;
+        self.highlighting.clear();
+    }
+
+    /// A load is over when Agda sends the goals (or the compilation result).
+    fn finish_load(&mut self, ok: bool) {
+        if self.loading {
+            self.loads += 1;
+            if ok {
+                self.buffer_changed = false;
+            }
+        }
+        self.loading = false;
+    }
+
+    fn observe_display_info(&mut self, info: &DisplayInfo) {
+        match info {
+            DisplayInfo::AllGoalsWarnings(agw) => {
+                self.goal_types = agw
+                    .visible_goals
+                    .iter()
+                    .filter_map(|goal| goal.try_as_of_type().ok())
+                    .map(|o| (o.constraint_obj.id, o.r#type.clone()))
+                    .collect();
+                if !agw.errors.is_empty() {
+                    let errors: Vec<&str> = agw.errors.iter().map(|e| e.message.as_str()).collect();
+                    self.last_error = Some(errors.join("\n"));
+                }
+                self.finish_load(agw.errors.is_empty());
+            }
+            // Compiling loads the file first, but Agda sends no goals after it.
+            DisplayInfo::CompilationOk { .. } => self.finish_load(true),
+            DisplayInfo::Error { error } => {
+                self.loading = false;
+                self.last_error = Some(error.clone().into());
+            }
+            _ => {}
+        }
+    }
+}
+
+impl ReplState {
+    pub(super) fn observe_session(&mut self, resp: &Resp) {
+        match resp {
+            Resp::Status { status } => self.session.status = Some(status.clone()),
+            Resp::InteractionPoints { interaction_points } => {
+                self.observe_interaction_points(interaction_points)
+            }
+            Resp::DisplayInfo { info: Some(info) } => self.observe_display_info(info),
+            Resp::HighlightingInfo(info) => {
+                if let Ok(highlighting) = info.read() {
+                    if highlighting.remove {
+                        self.session.highlighting.clear();
+                    }
+                    self.session.highlighting.extend(highlighting.payload);
+                }
+            }
+            Resp::ClearHighlighting { .. } => self.session.highlighting.clear(),
+            _ => {}
+        }
+    }
+
+    fn observe_display_info(&mut self, info: &DisplayInfo) {
+        self.session.observe_display_info(info);
+        match info {
+            DisplayInfo::InferredType(it) => self.observe_command_state(&it.command_state),
+            DisplayInfo::NormalForm(nf) => self.observe_command_state(&nf.command_state),
+            _ => {}
+        }
+    }
+
+    fn observe_command_state(&mut self, state: &CommandState) {
+        self.session.current_file = Some(state.current_file.clone());
+        self.observe_interaction_points(&state.interaction_points);
+    }
+
+    /// After an edit, Agda's ranges are outdated until the next load,
+    /// so only the goals that are gone are taken from Agda.
+    fn observe_interaction_points(&mut self, points: &[InteractionPoint]) {
+        if self.session.goals_shifted {
+            retain_goals(&mut self.interaction_points, points);
+        } else {
+            self.interaction_points = points.to_vec();
+        }
+    }
+
+    /// Called when any command is sent.
+    pub(super) fn begin_command(&mut self) {
+        self.session.last_error = None;
+    }
+
+    /// Called when a load command is sent.
+    pub(super) fn begin_load(&mut self) {
+        self.session.begin_load();
+        self.module_graph.begin_load();
+    }
+
+    /// The latest [`Resp::Status`](crate::resp::Resp::Status).
+    pub fn status(&self) -> Option<&Status> {
+        self.session.status.as_ref()
+    }
+
+    /// The current file, as Agda reports in [`CommandState`](crate::resp::CommandState).
+    pub fn current_file(&self) -> Option<&str> {
+        self.session.current_file.as_deref()
+    }
+
+    /// The goals, with their types when known.
+    pub fn goals(&self) -> Vec<Goal> {
+        self.interaction_points
+            .iter()
+            .map(|ip| Goal {
+                interaction_point: ip.clone(),
+                r#type: self.session.goal_types.get(&ip.id).cloned(),
+            })
+            .collect()
+    }
+
+    pub fn goal(&self, id: InteractionId) -> Option<Goal> {
+        self.goals().into_iter().find(|g| g.id() == id)
+    }
+
+    /// Keep the goal ranges in sync with edits on the source,
+    /// `new_source` is the text after the edits.
+    pub fn shift_goals(&mut self, edits: &[TextEdit], new_source: &str) {
+        shift_interaction_points(&mut self.interaction_points, edits, new_source);
+        self.session.goals_shifted = true;
+    }
+
+    /// The error reported for the last command, if it failed,
+    /// either as an error or as errors in `AllGoalsWarnings`.
+    pub fn last_error(&self) -> Option<&str> {
+        self.session.last_error.as_deref()
+    }
+
+    /// The highlighting of the current file, sent while loading.
+    pub fn highlighting(&self) -> &[AspectHighlight] {
+        &self.session.highlighting
+    }
+
+    /// How many loads have finished, for caching things per load.
+    pub fn load_count(&self) -> usize {
+        self.session.loads
+    }
+
+    /// Has the buffer changed since the last successful load?
+    pub fn buffer_changed(&self) -> bool {
+        self.session.buffer_changed
+    }
+
+    /// The error and the error jump of the last command.
+    /// Sending a command clears them, so save them before sending
+    /// commands the user hasn't typed, like the queries for completion.
+    pub fn save_error(&self) -> SavedError {
+        SavedError {
+            error: self.session.last_error.clone(),
+            jump: self.error_jump.clone(),
+        }
+    }
+
+    pub fn restore_error(&mut self, saved: SavedError) {
+        self.session.last_error = saved.error;
+        self.error_jump = saved.jump;
+    }
+
+    /// Tell the state that the buffer is edited without telling Agda.
+    pub fn mark_buffer_changed(&mut self) {
+        self.session.buffer_changed = true;
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::resp::{AgdaError, AllGoalsWarnings, TCWarning};
+
+    fn all_goals_warnings(errors: &[&str]) -> DisplayInfo {
+        DisplayInfo::AllGoalsWarnings(AllGoalsWarnings {
+            visible_goals: vec![],
+            invisible_goals: vec![],
+            warnings: vec![],
+            errors: errors
+                .iter()
+                .map(|e| TCWarning {
+                    message: e.to_string(),
+                })
+                .collect(),
+        })
+    }
+
+    #[test]
+    fn load() {
+        let mut session = Session {
+            buffer_changed: true,
+            ..Default::default()
+        };
+        session.begin_load();
+        assert!(session.loading);
+        session.observe_display_info(&all_goals_warnings(&[]));
+        assert!(!session.loading);
+        assert!(!session.buffer_changed);
+        assert_eq!(session.loads, 1);
+        // Like `metas`, not a load.
+        session.observe_display_info(&all_goals_warnings(&[]));
+        assert_eq!(session.loads, 1);
+    }
+
+    #[test]
+    fn load_failed() {
+        let mut session = Session {
+            buffer_changed: true,
+            ..Default::default()
+        };
+        session.begin_load();
+        session.observe_display_info(&all_goals_warnings(&["Unsolved metas"]));
+        assert_eq!(session.loads, 1);
+        assert!(session.buffer_changed);
+        assert_eq!(session.last_error.as_deref(), Some("Unsolved metas"));
+        session.begin_load();
+        let error = AgdaError {
+            message: Some("Parse error".to_owned()),
+        };
+        session.observe_display_info(&DisplayInfo::Error { error });
+        assert!(!session.loading);
+        assert_eq!(session.loads, 1);
+        assert_eq!(session.last_error.as_deref(), Some("Parse error"));
+    }
+
+    #[test]
+    fn compile() {
+        let mut session = Session::default();
+        session.begin_load();
+        let ok = DisplayInfo::CompilationOk {
+            warnings: String::new(),
+            errors: String::new(),
+        };
+        session.observe_display_info(&ok);
+        assert!(!session.loading);
+        assert_eq!(session.loads, 1);
+        // Like `metas` after the compilation.
+        session.observe_display_info(&all_goals_warnings(&[]));
+        assert_eq!(session.loads, 1);
+    }
+}
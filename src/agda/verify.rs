@@ -27,9 +27,8 @@ impl ReplState {
     pub async fn validate_version(&mut self) -> io::Result<()> {
         self.command(Cmd::ShowVersion).await?;
         let version = loop {
-            match self.next_display_info().await? {
-                DisplayInfo::Version { version } => break version,
-                _ => {}
+            if let DisplayInfo::Version { version } = self.next_display_info().await? {
+                break version;
             }
         };
         // other checks?
@@ -3,36 +3,26 @@ use std::fmt::{Display, Error, Formatter};
 
 /// Modifier for interactive commands,
 /// specifying the amount of normalization in the output.
-#[derive(Deserialize, Debug, Copy, Clone, Eq, PartialEq, Ord, PartialOrd, Hash)]
+#[derive(Deserialize, Debug, Default, Copy, Clone, Eq, PartialEq, Ord, PartialOrd, Hash)]
 pub enum Rewrite {
     AsIs,
     Instantiated,
     HeadNormal,
+    #[default]
     Simplified,
     Normalised,
 }
 
-impl Default for Rewrite {
-    fn default() -> Self {
-        Rewrite::Simplified
-    }
-}
-
 /// Modifier for the interactive computation command,
 /// specifying the mode of computation and result display.
-#[derive(Deserialize, Debug, Copy, Clone, Eq, PartialEq, Ord, PartialOrd, Hash)]
+#[derive(Deserialize, Debug, Default, Copy, Clone, Eq, PartialEq, Ord, PartialOrd, Hash)]
 pub enum ComputeMode {
+    #[default]
     DefaultCompute,
     IgnoreAbstract,
     UseShowInstance,
 }
 
-impl Default for ComputeMode {
-    fn default() -> Self {
-        ComputeMode::DefaultCompute
-    }
-}
-
 #[derive(Deserialize, Debug, Copy, Clone, Eq, PartialEq, Ord, PartialOrd, Hash)]
 pub enum Comparison {
     CmpEq,
@@ -107,18 +97,13 @@ pub enum Remove {
 
 /// Is the highlighting "token-based", i.e. based only on
 /// information from the lexer?
-#[derive(Deserialize, Debug, Copy, Clone, Eq, PartialEq, Ord, PartialOrd, Hash)]
+#[derive(Deserialize, Debug, Default, Copy, Clone, Eq, PartialEq, Ord, PartialOrd, Hash)]
 pub enum TokenBased {
     TokenBased,
+    #[default]
     NotOnlyTokenBased,
 }
 
-impl Default for TokenBased {
-    fn default() -> Self {
-        TokenBased::NotOnlyTokenBased
-    }
-}
-
 #[derive(Deserialize, Debug, Copy, Clone, Eq, PartialEq, Ord, PartialOrd, Hash)]
 pub enum Hiding {
     YesOverlap,
@@ -0,0 +1,181 @@
+use std::fmt::{Display, Error, Formatter};
+use std::path::PathBuf;
+use std::str::FromStr;
+
+/// Options of the GHC backends.
+#[derive(Debug, Clone, Default, Eq, PartialEq)]
+pub struct GhcOptions {
+    /// `--compile-dir`.
+    pub compile_dir: Option<PathBuf>,
+    /// `--ghc-dont-call-ghc`, only generate the Haskell code.
+    pub dont_call_ghc: bool,
+    /// `--ghc-flag`, passed to GHC.
+    pub ghc_flags: Vec<String>,
+}
+
+/// Options of the JavaScript backend.
+#[derive(Debug, Clone, Default, Eq, PartialEq)]
+pub struct JsOptions {
+    /// `--compile-dir`.
+    pub compile_dir: Option<PathBuf>,
+    /// `--js-optimize`.
+    pub optimize: bool,
+    /// `--js-minify`.
+    pub minify: bool,
+}
+
+/// Options of the LaTeX backends.
+#[derive(Debug, Clone, Default, Eq, PartialEq)]
+pub struct LaTeXOptions {
+    /// `--latex-dir`.
+    pub latex_dir: Option<PathBuf>,
+}
+
+/// Options of the HTML backend.
+#[derive(Debug, Clone, Default, Eq, PartialEq)]
+pub struct HtmlOptions {
+    /// `--html-dir`.
+    pub html_dir: Option<PathBuf>,
+    /// `--html-highlight`, one of `code`, `all` or `auto`.
+    pub highlight: Option<String>,
+    /// `--css`.
+    pub css: Option<String>,
+}
+
+/// The backend of [`Compile`](super::Cmd::Compile), with its options.
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub enum Backend {
+    GHC(GhcOptions),
+    /// GHC, but the module doesn't need a `main`.
+    GHCNoMain(GhcOptions),
+    JS(JsOptions),
+    LaTeX(LaTeXOptions),
+    QuickLaTeX(LaTeXOptions),
+    HTML(HtmlOptions),
+    /// A backend Agda is extended with, its options are given as raw flags.
+    Other {
+        name: String,
+        flags: Vec<String>,
+    },
+}
+
+fn dir_flag(flags: &mut Vec<String>, flag: &str, dir: &Option<PathBuf>) {
+    if let Some(dir) = dir {
+        flags.push(format!("{}={}", flag, dir.display()));
+    }
+}
+
+impl Backend {
+    /// The name Agda knows the backend by.
+    pub fn name(&self) -> &str {
+        use Backend::*;
+        match self {
+            GHC(..) => "GHC",
+            GHCNoMain(..) => "GHCNoMain",
+            JS(..) => "JS",
+            LaTeX(..) => "LaTeX",
+            QuickLaTeX(..) => "QuickLaTeX",
+            HTML(..) => "HTML",
+            Other { name, .. } => name,
+        }
+    }
+
+    /// The command-line options for the backend.
+    pub fn flags(&self) -> Vec<String> {
+        use Backend::*;
+        let mut flags = Vec::new();
+        match self {
+            GHC(ghc) | GHCNoMain(ghc) => {
+                dir_flag(&mut flags, "--compile-dir", &ghc.compile_dir);
+                if ghc.dont_call_ghc {
+                    flags.push("--ghc-dont-call-ghc".to_owned());
+                }
+                for flag in &ghc.ghc_flags {
+                    flags.push(format!("--ghc-flag={}", flag));
+                }
+            }
+            JS(js) => {
+                dir_flag(&mut flags, "--compile-dir", &js.compile_dir);
+                if js.optimize {
+                    flags.push("--js-optimize".to_owned());
+                }
+                if js.minify {
+                    flags.push("--js-minify".to_owned());
+                }
+            }
+            LaTeX(latex) | QuickLaTeX(latex) => {
+                dir_flag(&mut flags, "--latex-dir", &latex.latex_dir)
+            }
+            HTML(html) => {
+                dir_flag(&mut flags, "--html-dir", &html.html_dir);
+                if let Some(highlight) = &html.highlight {
+                    flags.push(format!("--html-highlight={}", highlight));
+                }
+                if let Some(css) = &html.css {
+                    flags.push(format!("--css={}", css));
+                }
+            }
+            Other { flags: other, .. } => flags.extend(other.iter().cloned()),
+        }
+        flags
+    }
+}
+
+/// Agda reads the backend as a bare word.
+impl Display for Backend {
+    fn fmt(&self, f: &mut Formatter) -> Result<(), Error> {
+        f.write_str(self.name())
+    }
+}
+
+/// Case-insensitive, with default options.
+/// Unknown names are [`Other`](Backend::Other) backends,
+/// they must be words, since Agda reads the name unquoted.
+impl FromStr for Backend {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        use Backend::*;
+        let is_word = !s.is_empty() && s.chars().all(|c| c.is_alphanumeric() || "-_".contains(c));
+        if !is_word {
+            return Err(format!("Invalid backend name `{}`.", s));
+        }
+        Ok(match s.to_lowercase().as_str() {
+            "ghc" => GHC(Default::default()),
+            "ghcnomain" | "ghc-no-main" => GHCNoMain(Default::default()),
+            "js" => JS(Default::default()),
+            "latex" => LaTeX(Default::default()),
+            "quicklatex" => QuickLaTeX(Default::default()),
+            "html" => HTML(Default::default()),
+            _ => Other {
+                name: s.to_owned(),
+                flags: vec![],
+            },
+        })
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::cmd::Cmd;
+
+    #[test]
+    fn compile() {
+        let backend = Backend::GHC(GhcOptions {
+            dont_call_ghc: true,
+            ghc_flags: vec!["-O2".to_owned()],
+            ..Default::default()
+        });
+        let cmd = Cmd::compile(backend, PathBuf::from("A.agda"), vec!["--safe".to_owned()]);
+        assert_eq!(
+            cmd.to_string(),
+            r#"( Cmd_compile GHC "A.agda" ["--safe", "--ghc-dont-call-ghc", "--ghc-flag=-O2"] )"#
+        );
+        assert_eq!("latex".parse(), Ok(Backend::LaTeX(Default::default())));
+        let other: Backend = "Agda2Lambox".parse().unwrap();
+        assert_eq!(other.name(), "Agda2Lambox");
+        assert!("\"GHC\"".parse::<Backend>().is_err());
+        assert!("My Backend".parse::<Backend>().is_err());
+    }
+}
@@ -16,9 +16,10 @@ impl Display for IOTCM {
 }
 
 /// How much highlighting should be sent to the user interface?
-#[derive(Debug, Copy, Clone, Eq, PartialEq, Ord, PartialOrd, Hash)]
+#[derive(Debug, Default, Copy, Clone, Eq, PartialEq, Ord, PartialOrd, Hash)]
 pub enum HighlightingLevel {
     None,
+    #[default]
     NonInteractive,
     /// This includes both non-interactive highlighting and
     /// interactive highlighting of the expression that is currently
@@ -26,27 +27,16 @@ pub enum HighlightingLevel {
     Interactive,
 }
 
-impl Default for HighlightingLevel {
-    fn default() -> Self {
-        HighlightingLevel::NonInteractive
-    }
-}
-
 /// How should highlighting be sent to the user interface?
-#[derive(Debug, Copy, Clone, Eq, PartialEq, Ord, PartialOrd, Hash)]
+#[derive(Debug, Default, Copy, Clone, Eq, PartialEq, Ord, PartialOrd, Hash)]
 pub enum HighlightingMethod {
     /// Via stdout.
+    #[default]
     Direct,
     /// Both via files and via stdout.
     Indirect,
 }
 
-impl Default for HighlightingMethod {
-    fn default() -> Self {
-        HighlightingMethod::Direct
-    }
-}
-
 #[derive(Debug, Clone)]
 pub struct IOTCM {
     level: HighlightingLevel,
@@ -75,6 +65,7 @@ impl IOTCM {
     }
 
     /// Convert `self` into a command string.
+    #[allow(clippy::inherent_to_string_shadow_display)]
     pub fn to_string(&self) -> String {
         format!("{}\n", self)
     }
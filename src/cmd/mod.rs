@@ -4,11 +4,17 @@ use std::path::PathBuf;
 use crate::base::{ComputeMode, Remove, Rewrite, UseForce};
 use crate::hs::HaskellBool;
 
+pub use self::backend::*;
 pub use self::goal::*;
 pub use self::iotcm::*;
+pub use self::options::*;
 
+/// Compiler backends and their options.
+mod backend;
 mod goal;
 mod iotcm;
+/// Command-line options of Agda.
+mod options;
 
 #[derive(Debug, Clone)]
 pub enum Cmd {
@@ -19,9 +25,10 @@ pub enum Cmd {
         flags: Vec<String>,
     },
     /// Compiles the module in file `path` using
-    /// the backend `backend`, using `flags` as the command-line options.
+    /// the backend `backend`, using `flags` and the backend's options
+    /// as the command-line options.
     Compile {
-        backend: String,
+        backend: Backend,
         path: PathBuf,
         flags: Vec<String>,
     },
@@ -167,6 +174,15 @@ impl Cmd {
         }
     }
 
+    /// Produces [CompilationOk](crate::resp::DisplayInfo::CompilationOk).
+    pub fn compile(backend: Backend, path: PathBuf, flags: Vec<String>) -> Self {
+        Cmd::Compile {
+            backend,
+            path,
+            flags,
+        }
+    }
+
     /// Produces [CurrentGoal](crate::resp::GoalInfo::CurrentGoal).
     pub fn goal_type(input: GoalInput) -> Self {
         Cmd::GoalType(From::from(input))
@@ -187,11 +203,30 @@ impl Cmd {
         }
     }
 
+    /// Produces [SearchAbout](crate::resp::DisplayInfo::SearchAbout).
+    pub fn search_about(search: String) -> Self {
+        Cmd::SearchAboutToplevel {
+            rewrite: Default::default(),
+            search,
+        }
+    }
+
+    /// Contents of a module in the scope of a goal,
+    /// produces [ModuleContents](crate::resp::DisplayInfo::ModuleContents).
+    pub fn module_contents(input: GoalInput) -> Self {
+        Cmd::ShowModuleContents(From::from(input))
+    }
+
     /// Produces [InferredType](crate::resp::GoalInfo::InferredType).
     pub fn infer(input: GoalInput) -> Self {
         Cmd::Infer(From::from(input))
     }
 
+    /// Produces [SolveAll](crate::resp::Resp::SolveAll).
+    pub fn solve_one(input: GoalInput) -> Self {
+        Cmd::SolveOne(From::from(input))
+    }
+
     pub fn give(input: GoalInput) -> Self {
         Cmd::Give {
             force: UseForce::WithoutForce,
@@ -209,13 +244,16 @@ impl Display for Cmd {
                 backend,
                 path,
                 flags,
-            } => write!(
-                f,
-                "( Cmd_compile {:?} \"{}\" {:?} )",
-                backend,
-                path.display(),
-                flags
-            ),
+            } => {
+                let flags: Vec<String> = flags.iter().cloned().chain(backend.flags()).collect();
+                write!(
+                    f,
+                    "( Cmd_compile {} \"{}\" {:?} )",
+                    backend,
+                    path.display(),
+                    flags
+                )
+            }
             Constraints => f.write_str("Cmd_constraints"),
             Metas => f.write_str("Cmd_metas"),
             ShowModuleContentsToplevel { rewrite, search } => write!(
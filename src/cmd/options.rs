@@ -0,0 +1,214 @@
+use std::path::PathBuf;
+
+/// Command-line options for [`Load`](super::Cmd::Load), built like:
+///
+/// ```
+/// use agda_mode::cmd::AgdaOptions;
+/// let flags = AgdaOptions::new().cubical(true).library("cubical").flags();
+/// assert_eq!(flags.unwrap(), vec!["--library=cubical", "--cubical"]);
+/// ```
+#[derive(Debug, Clone, Default, Eq, PartialEq)]
+pub struct AgdaOptions {
+    include_paths: Vec<PathBuf>,
+    libraries: Vec<String>,
+    safe: bool,
+    without_k: bool,
+    cubical: bool,
+    guardedness: bool,
+    sized_types: bool,
+    verbosity: Vec<(String, u32)>,
+}
+
+impl AgdaOptions {
+    pub fn new() -> Self {
+        Default::default()
+    }
+
+    /// `--include-path`, can be used several times.
+    pub fn include_path(mut self, path: impl Into<PathBuf>) -> Self {
+        self.include_paths.push(path.into());
+        self
+    }
+
+    /// `--library`, can be used several times.
+    pub fn library(mut self, library: impl Into<String>) -> Self {
+        self.libraries.push(library.into());
+        self
+    }
+
+    /// `--safe`.
+    pub fn safe(mut self, safe: bool) -> Self {
+        self.safe = safe;
+        self
+    }
+
+    /// `--without-K`.
+    pub fn without_k(mut self, without_k: bool) -> Self {
+        self.without_k = without_k;
+        self
+    }
+
+    /// `--cubical`, which implies `--without-K`.
+    pub fn cubical(mut self, cubical: bool) -> Self {
+        self.cubical = cubical;
+        self
+    }
+
+    /// `--guardedness`.
+    pub fn guardedness(mut self, guardedness: bool) -> Self {
+        self.guardedness = guardedness;
+        self
+    }
+
+    /// `--sized-types`.
+    pub fn sized_types(mut self, sized_types: bool) -> Self {
+        self.sized_types = sized_types;
+        self
+    }
+
+    /// `--verbose=key:level`, like `tc.meta:20`.
+    pub fn verbose(mut self, key: impl Into<String>, level: u32) -> Self {
+        self.verbosity.push((key.into(), level));
+        self
+    }
+
+    /// Reject the malformed options, the ones given twice,
+    /// and the combination that `--safe` rejects (sized types with guardedness).
+    /// Implied options, like `--without-K` with `--cubical`, are fine.
+    pub fn validate(&self) -> Result<(), String> {
+        if self.safe && self.sized_types && self.guardedness {
+            return Err(
+                "`--safe` cannot be used with both `--sized-types` and `--guardedness`.".to_owned(),
+            );
+        }
+        if self.include_paths.iter().any(|p| p.as_os_str().is_empty()) {
+            return Err("Empty include path.".to_owned());
+        }
+        if let Some(path) = duplicate(&self.include_paths) {
+            return Err(format!("Include path `{}` is given twice.", path.display()));
+        }
+        if let Some(library) = self
+            .libraries
+            .iter()
+            .find(|l| l.trim().is_empty() || l.contains(char::is_whitespace))
+        {
+            return Err(format!("Invalid library name `{}`.", library));
+        }
+        if let Some(library) = duplicate(&self.libraries) {
+            return Err(format!("Library `{}` is given twice.", library));
+        }
+        // The empty key sets the level of everything.
+        let invalid_key = |k: &str| {
+            !k.is_empty()
+                && (k.contains(|c: char| c.is_whitespace() || c == ':')
+                    || k.split('.').any(str::is_empty))
+        };
+        if let Some((key, _)) = self.verbosity.iter().find(|(k, _)| invalid_key(k)) {
+            return Err(format!("Invalid verbosity key `{}`.", key));
+        }
+        let keys: Vec<&String> = self.verbosity.iter().map(|(k, _)| k).collect();
+        if let Some(key) = duplicate(&keys) {
+            return Err(format!("Verbosity key `{}` is given twice.", key));
+        }
+        Ok(())
+    }
+
+    /// The flags for Agda, if the options are [valid](Self::validate).
+    pub fn flags(&self) -> Result<Vec<String>, String> {
+        self.validate()?;
+        let mut flags = Vec::new();
+        for path in &self.include_paths {
+            flags.push(format!("--include-path={}", path.display()));
+        }
+        for library in &self.libraries {
+            flags.push(format!("--library={}", library));
+        }
+        let switches = [
+            (self.safe, "--safe"),
+            (self.without_k, "--without-K"),
+            (self.cubical, "--cubical"),
+            (self.guardedness, "--guardedness"),
+            (self.sized_types, "--sized-types"),
+        ];
+        for (on, flag) in &switches {
+            if *on {
+                flags.push(flag.to_string());
+            }
+        }
+        for (key, level) in &self.verbosity {
+            if key.is_empty() {
+                flags.push(format!("--verbose={}", level));
+            } else {
+                flags.push(format!("--verbose={}:{}", key, level));
+            }
+        }
+        Ok(flags)
+    }
+}
+
+/// The first item that appears twice.
+fn duplicate<T: Eq>(items: &[T]) -> Option<&T> {
+    items
+        .iter()
+        .enumerate()
+        .find(|(i, item)| items[..*i].contains(item))
+        .map(|(_, item)| item)
+}
+
+#[cfg(test)]
+mod test {
+    use super::AgdaOptions;
+
+    #[test]
+    fn flags() {
+        let options = AgdaOptions::new()
+            .include_path("src")
+            .safe(true)
+            .without_k(true)
+            .verbose("tc.meta", 20);
+        assert_eq!(
+            options.flags().unwrap(),
+            vec![
+                "--include-path=src",
+                "--safe",
+                "--without-K",
+                "--verbose=tc.meta:20"
+            ]
+        );
+        let options = options.sized_types(true).guardedness(true);
+        assert!(options.flags().is_err());
+        assert!(AgdaOptions::new().library("").validate().is_err());
+    }
+
+    #[test]
+    fn rejected() {
+        let twice = AgdaOptions::new()
+            .library("std")
+            .library("cubical")
+            .library("std");
+        assert_eq!(
+            twice.validate().unwrap_err(),
+            "Library `std` is given twice."
+        );
+        let twice = AgdaOptions::new().include_path("src").include_path("src");
+        assert!(twice.validate().is_err());
+        assert!(AgdaOptions::new().include_path("").validate().is_err());
+        assert!(AgdaOptions::new()
+            .library("agda stdlib")
+            .validate()
+            .is_err());
+        let twice = AgdaOptions::new().verbose("tc", 10).verbose("tc", 20);
+        assert!(twice.validate().is_err());
+        for key in ["tc..meta", "tc.", "tc:meta", "tc meta"] {
+            let options = AgdaOptions::new().verbose(key, 10);
+            assert!(options.validate().is_err(), "{}", key);
+        }
+        let options = AgdaOptions::new().verbose("", 1).verbose("tc.meta", 20);
+        assert!(options.validate().is_ok());
+        // `--cubical` implies `--without-K`.
+        let cubical = AgdaOptions::new().safe(true).cubical(true).without_k(true);
+        assert!(cubical.validate().is_ok());
+        let options = AgdaOptions::new().safe(true).sized_types(true);
+        assert!(options.guardedness(true).validate().is_err());
+    }
+}
@@ -2,9 +2,11 @@ use std::sync::Mutex;
 
 use lazy_static::lazy_static;
 
+type DebugHandler = Mutex<Option<Box<dyn Fn(String) + Send>>>;
+
 lazy_static! {
-    static ref DEBUG_COMMAND: Mutex<Option<Box<dyn Fn(String) + Send>>> = Mutex::new(None);
-    static ref DEBUG_RESPONSE: Mutex<Option<Box<dyn Fn(String) + Send>>> = Mutex::new(None);
+    static ref DEBUG_COMMAND: DebugHandler = Mutex::new(None);
+    static ref DEBUG_RESPONSE: DebugHandler = Mutex::new(None);
 }
 
 pub fn debug_command_via(f: impl Fn(String) + Send + 'static) {
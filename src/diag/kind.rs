@@ -0,0 +1,216 @@
+use crate::resp::AgdaError;
+
+use super::Diagnostic;
+
+/// Classification of common Agda errors.
+/// The fields are extracted from the message when possible.
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub enum ErrorKind {
+    /// `Not in scope: x`.
+    NotInScope {
+        names: Vec<String>,
+    },
+    /// `a != b of type T`.
+    UnequalTerms {
+        left: String,
+        right: String,
+        r#type: Option<String>,
+    },
+    /// Incomplete pattern matching (coverage failure).
+    MissingClauses {
+        function: Option<String>,
+        cases: Vec<String>,
+    },
+    TerminationFailure {
+        functions: Vec<String>,
+    },
+    /// The locations of the unsolved metas, as printed by Agda.
+    UnsolvedMetas {
+        locations: Vec<String>,
+    },
+    /// `Failed to solve the following constraints:`.
+    UnsolvedConstraints,
+    /// `near` is the text Agda marked with `<ERROR>`.
+    ParseError {
+        near: Option<String>,
+    },
+    ModuleNotFound {
+        module: String,
+    },
+    AmbiguousName {
+        name: String,
+        candidates: Vec<String>,
+    },
+    Other,
+}
+
+impl ErrorKind {
+    /// Classify an error message, with the location header already removed
+    /// (see [`Diagnostic::parse`](super::Diagnostic::parse)).
+    pub fn classify(message: &str) -> Self {
+        let message = message.trim_start();
+        let mut lines = message.lines();
+        let first = lines.next().unwrap_or_default();
+        if first.starts_with("Not in scope") {
+            let names = indented(lines).map(|l| first_word(l).to_owned()).collect();
+            ErrorKind::NotInScope { names }
+        } else if first.starts_with("Incomplete pattern matching for") {
+            let function = first
+                .trim_start_matches("Incomplete pattern matching for")
+                .split('.')
+                .next()
+                .map(|s| s.trim().to_owned())
+                .filter(|s| !s.is_empty());
+            let cases = indented(lines).map(|l| l.trim().to_owned()).collect();
+            ErrorKind::MissingClauses { function, cases }
+        } else if first.starts_with("Termination checking failed") {
+            let functions = indented(lines)
+                .next()
+                .map(|l| l.split(',').map(|f| f.trim().to_owned()).collect())
+                .unwrap_or_default();
+            ErrorKind::TerminationFailure { functions }
+        } else if first.starts_with("Unsolved metas") {
+            let locations = indented(lines).map(|l| l.trim().to_owned()).collect();
+            ErrorKind::UnsolvedMetas { locations }
+        } else if first.starts_with("Failed to solve the following constraints") {
+            ErrorKind::UnsolvedConstraints
+        } else if first.starts_with("Parse error") {
+            let near = lines
+                .next()
+                .map(|l| l.trim().trim_end_matches("<ERROR>").trim().to_owned())
+                .or_else(|| Some(first.trim_start_matches("Parse error").trim().to_owned()))
+                .filter(|s| !s.is_empty());
+            ErrorKind::ParseError { near }
+        } else if first.starts_with("Failed to find source of module") {
+            let module = first
+                .trim_start_matches("Failed to find source of module")
+                .trim_start();
+            let module = first_word(module).to_owned();
+            ErrorKind::ModuleNotFound { module }
+        } else if first.starts_with("Ambiguous name") {
+            let name = first.trim_start_matches("Ambiguous name").trim_start();
+            let name = first_word(name).trim_end_matches('.').to_owned();
+            let candidates = indented(lines).map(|l| first_word(l).to_owned()).collect();
+            ErrorKind::AmbiguousName { name, candidates }
+        } else {
+            unequal_terms(message).unwrap_or(ErrorKind::Other)
+        }
+    }
+}
+
+/// The first word of an indented line, like `x` in `  x at /path:1,1-2`.
+fn first_word(line: &str) -> &str {
+    line.split_whitespace().next().unwrap_or_default()
+}
+
+/// The block of indented lines following the first line.
+fn indented<'a>(lines: impl Iterator<Item = &'a str>) -> impl Iterator<Item = &'a str> {
+    lines.take_while(|l| l.starts_with(char::is_whitespace) && !l.trim().is_empty())
+}
+
+/// The first paragraph, before `when checking ...`, looks like `a != b of type T`.
+/// The later ones may be constraints or context, which can contain `!=` as well.
+fn unequal_terms(message: &str) -> Option<ErrorKind> {
+    let paragraph = message
+        .lines()
+        .take_while(|l| !l.trim().is_empty() && !l.starts_with("when "))
+        .collect::<Vec<_>>()
+        .join("\n");
+    let paragraph = paragraph.trim();
+    let idx = paragraph.find(" != ")?;
+    let left = paragraph[..idx].trim().to_owned();
+    let rest = &paragraph[idx + 4..];
+    let (right, r#type) = match rest.rfind(" of type ") {
+        Some(idx) => (&rest[..idx], Some(rest[idx + 9..].trim().to_owned())),
+        None => (rest, None),
+    };
+    Some(ErrorKind::UnequalTerms {
+        left,
+        right: right.trim().to_owned(),
+        r#type,
+    })
+}
+
+impl Diagnostic {
+    pub fn kind(&self) -> ErrorKind {
+        ErrorKind::classify(&self.message)
+    }
+}
+
+impl AgdaError {
+    pub fn kind(&self) -> ErrorKind {
+        self.diagnostic().kind()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::diag::Severity;
+
+    fn kind_of(message: &str) -> ErrorKind {
+        Diagnostic::parse(Severity::Error, message).kind()
+    }
+
+    #[test]
+    fn not_in_scope() {
+        let kind = kind_of(
+            "/a/B.agda:3,7-10\nNot in scope:\n  foo at /a/B.agda:3,7-10\nwhen scope checking foo",
+        );
+        let names = vec!["foo".to_owned()];
+        assert_eq!(kind, ErrorKind::NotInScope { names });
+    }
+
+    #[test]
+    fn unequal_terms() {
+        let kind = kind_of("/a/B.agda:5,9-13\nzero != suc n of type ℕ\nwhen checking that the expression zero has type ℕ");
+        let expected = ErrorKind::UnequalTerms {
+            left: "zero".to_owned(),
+            right: "suc n".to_owned(),
+            r#type: Some("ℕ".to_owned()),
+        };
+        assert_eq!(kind, expected);
+    }
+
+    #[test]
+    fn missing_clauses() {
+        let kind = kind_of("/a/B.agda:5,1-9\nIncomplete pattern matching for f. Missing cases:\n  f zero\nwhen checking the definition of f");
+        let expected = ErrorKind::MissingClauses {
+            function: Some("f".to_owned()),
+            cases: vec!["f zero".to_owned()],
+        };
+        assert_eq!(kind, expected);
+    }
+
+    #[test]
+    fn termination() {
+        let kind = kind_of("/a/B.agda:5,1-9\nTermination checking failed for the following functions:\n  f, g\nProblematic calls:\n  f (suc n)");
+        let functions = vec!["f".to_owned(), "g".to_owned()];
+        assert_eq!(kind, ErrorKind::TerminationFailure { functions });
+    }
+
+    #[test]
+    fn unsolved_constraints() {
+        let kind = kind_of("/a/B.agda:5,9-13\nFailed to solve the following constraints:\n  zero != suc _n_5 of type ℕ\n    (blocked on _n_5, belongs to problem 3)");
+        assert_eq!(kind, ErrorKind::UnsolvedConstraints);
+    }
+
+    #[test]
+    fn misc() {
+        let kind = kind_of("/a/B.agda:1,8-11\nFailed to find source of module Foo in any of the following\nlocations:");
+        let module = "Foo".to_owned();
+        assert_eq!(kind, ErrorKind::ModuleNotFound { module });
+        let kind = kind_of("/a/B.agda:4,3-4\nParse error\n=<ERROR>\n zero");
+        let near = Some("=".to_owned());
+        assert_eq!(kind, ErrorKind::ParseError { near });
+        let kind = kind_of("/a/B.agda:4,3-4\nAmbiguous name x. It could refer to any one of\n  A.x bound at /a/A.agda:3,1-2\n  C.x bound at /a/C.agda:3,1-2");
+        let expected = ErrorKind::AmbiguousName {
+            name: "x".to_owned(),
+            candidates: vec!["A.x".to_owned(), "C.x".to_owned()],
+        };
+        assert_eq!(kind, expected);
+        assert_eq!(kind_of("Something else"), ErrorKind::Other);
+        let kind = kind_of("/a/B.agda:4,3-4\nSomething else\n\n  zero != suc n of type ℕ");
+        assert_eq!(kind, ErrorKind::Other);
+    }
+}
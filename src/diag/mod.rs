@@ -0,0 +1,291 @@
+use std::fmt::{Display, Error, Formatter};
+
+use serde::Serialize;
+
+use crate::resp::{AgdaError, AllGoalsWarnings, Resp, TCWarning};
+
+pub use self::kind::*;
+
+/// Classification of common errors.
+mod kind;
+
+/// How bad a diagnostic is.
+#[derive(Serialize, Debug, Copy, Clone, Eq, PartialEq, Ord, PartialOrd, Hash)]
+#[serde(rename_all = "lowercase")]
+pub enum Severity {
+    Error,
+    Warning,
+}
+
+impl Display for Severity {
+    fn fmt(&self, f: &mut Formatter) -> Result<(), Error> {
+        f.write_str(match self {
+            Severity::Error => "error",
+            Severity::Warning => "warning",
+        })
+    }
+}
+
+/// A source interval as printed in Agda's messages.
+/// Lines and columns are 1-indexed, the end column is exclusive.
+///
+/// Agda prints it as `12,5-9` (single-line) or `12,5-14,2` (multi-line),
+/// newer versions use `.` instead of `,`.
+#[derive(Serialize, Debug, Copy, Clone, Default, Eq, PartialEq, Ord, PartialOrd, Hash)]
+#[serde(rename_all = "camelCase")]
+pub struct Span {
+    pub start_line: usize,
+    pub start_col: usize,
+    pub end_line: usize,
+    pub end_col: usize,
+}
+
+impl Span {
+    pub fn is_multi_line(&self) -> bool {
+        self.start_line != self.end_line
+    }
+
+    /// Parse the `line,col-col` / `line,col-line,col` / `line,col` forms.
+    pub fn parse(s: &str) -> Option<Self> {
+        fn line_col(s: &str) -> Option<(usize, usize)> {
+            let idx = s.find([',', '.'])?;
+            Some((s[..idx].parse().ok()?, s[idx + 1..].parse().ok()?))
+        }
+        let mut parts = s.splitn(2, '-');
+        let (start_line, start_col) = line_col(parts.next()?)?;
+        let (end_line, end_col) = match parts.next() {
+            None => (start_line, start_col),
+            Some(end) => match end.parse() {
+                Ok(end_col) => (start_line, end_col),
+                Err(_) => line_col(end)?,
+            },
+        };
+        Some(Span {
+            start_line,
+            start_col,
+            end_line,
+            end_col,
+        })
+    }
+}
+
+impl Display for Span {
+    fn fmt(&self, f: &mut Formatter) -> Result<(), Error> {
+        write!(f, "{},{}-", self.start_line, self.start_col)?;
+        if self.is_multi_line() {
+            write!(f, "{},", self.end_line)?;
+        }
+        write!(f, "{}", self.end_col)
+    }
+}
+
+/// An error or warning reported by Agda, with the location
+/// split out from the message text.
+#[derive(Serialize, Debug, Clone, Eq, PartialEq)]
+pub struct Diagnostic {
+    pub severity: Severity,
+    pub file: Option<String>,
+    pub span: Option<Span>,
+    /// The warning (or error) name, like `UnreachableClauses`.
+    /// Only available in newer Agda versions.
+    pub category: Option<String>,
+    /// The message body, without the location header.
+    pub message: String,
+}
+
+impl Diagnostic {
+    /// Parse a message starting with a location header, like
+    /// `/path/File.agda:12,5-9` or `/path/File.agda:6.1-7: error: [MissingDefinitions]`.
+    /// Messages without a recognizable header are kept as-is.
+    pub fn parse(severity: Severity, text: &str) -> Self {
+        let (first, rest) = match text.find('\n') {
+            Some(idx) => (&text[..idx], &text[idx + 1..]),
+            None => (text, ""),
+        };
+        let (header, tail) = split_severity(first);
+        match parse_location(header.trim_end()) {
+            Some((file, span)) => {
+                let (category, extra) = split_category(tail.trim());
+                let mut message = extra.to_owned();
+                if !message.is_empty() && !rest.is_empty() {
+                    message.push('\n');
+                }
+                message.push_str(rest);
+                Diagnostic {
+                    severity,
+                    file,
+                    span: Some(span),
+                    category,
+                    message,
+                }
+            }
+            None => Diagnostic {
+                severity,
+                file: None,
+                span: None,
+                category: None,
+                message: text.to_owned(),
+            },
+        }
+    }
+}
+
+impl Display for Diagnostic {
+    fn fmt(&self, f: &mut Formatter) -> Result<(), Error> {
+        if let Some(file) = &self.file {
+            write!(f, "{}:", file)?;
+        }
+        if let Some(span) = &self.span {
+            write!(f, "{}: ", span)?;
+        }
+        write!(f, "{}", self.severity)?;
+        if let Some(category) = &self.category {
+            write!(f, " [{}]", category)?;
+        }
+        write!(f, "\n{}", self.message)
+    }
+}
+
+/// Split `header: error: tail` into `header` and `tail`.
+fn split_severity(line: &str) -> (&str, &str) {
+    for marker in &[": error:", ": warning:"] {
+        if let Some(idx) = line.find(marker) {
+            return (&line[..idx], &line[idx + marker.len()..]);
+        }
+    }
+    (line, "")
+}
+
+/// A location is either a bare span, or `file:span`.
+fn parse_location(header: &str) -> Option<(Option<String>, Span)> {
+    if let Some(span) = Span::parse(header) {
+        return Some((None, span));
+    }
+    let idx = header.rfind(':')?;
+    let span = Span::parse(&header[idx + 1..])?;
+    Some((Some(header[..idx].to_owned()), span))
+}
+
+/// Extract `Name` from `[Name]` or `-W[no]Name`, returning the text after it.
+fn split_category(tail: &str) -> (Option<String>, &str) {
+    let name_end = |s: &str| s.find(char::is_whitespace).unwrap_or(s.len());
+    if let Some(s) = tail.strip_prefix('[') {
+        match s.find(']') {
+            Some(idx) => (Some(s[..idx].to_owned()), s[idx + 1..].trim_start()),
+            None => (None, tail),
+        }
+    } else if let Some(s) = tail.strip_prefix("-W[no]") {
+        let idx = name_end(s);
+        (Some(s[..idx].to_owned()), s[idx..].trim_start())
+    } else {
+        (None, tail)
+    }
+}
+
+impl AgdaError {
+    pub fn diagnostic(&self) -> Diagnostic {
+        let message = self.message.as_deref().unwrap_or("Unknown error");
+        Diagnostic::parse(Severity::Error, message)
+    }
+}
+
+impl TCWarning {
+    pub fn diagnostic(&self, severity: Severity) -> Diagnostic {
+        Diagnostic::parse(severity, &self.message)
+    }
+}
+
+impl AllGoalsWarnings {
+    /// Errors first, then warnings.
+    pub fn diagnostics(&self) -> Vec<Diagnostic> {
+        let errors = self.errors.iter().map(|e| e.diagnostic(Severity::Error));
+        let warnings = self
+            .warnings
+            .iter()
+            .map(|w| w.diagnostic(Severity::Warning));
+        errors.chain(warnings).collect()
+    }
+}
+
+/// The position [`Resp::JumpToError`](crate::resp::Resp::JumpToError) points to.
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct ErrorJump {
+    pub filepath: String,
+    /// 1-indexed character offset in the file.
+    pub position: i32,
+}
+
+impl Resp {
+    pub fn error_jump(&self) -> Option<ErrorJump> {
+        match self {
+            Resp::JumpToError { filepath, position } => Some(ErrorJump {
+                filepath: filepath.clone(),
+                position: *position,
+            }),
+            _ => None,
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn parse_single_line() {
+        let d = Diagnostic::parse(
+            Severity::Error,
+            "/path/File.agda:12,5-9\nNot in scope:\n  x",
+        );
+        assert_eq!(d.file.as_deref(), Some("/path/File.agda"));
+        let span = d.span.unwrap();
+        assert_eq!((span.start_line, span.start_col), (12, 5));
+        assert_eq!((span.end_line, span.end_col), (12, 9));
+        assert!(!span.is_multi_line());
+        assert_eq!(d.message, "Not in scope:\n  x");
+        assert_eq!(d.category, None);
+    }
+
+    #[test]
+    fn parse_multi_line() {
+        let d = Diagnostic::parse(Severity::Warning, "/a/B.agda:3,1-5,12\nUnreachable clause");
+        let span = d.span.unwrap();
+        assert_eq!((span.end_line, span.end_col), (5, 12));
+        assert!(span.is_multi_line());
+        assert_eq!(span.to_string(), "3,1-5,12");
+    }
+
+    #[test]
+    fn parse_no_file() {
+        let d = Diagnostic::parse(
+            Severity::Error,
+            "1,21-22\nGeneralizable variable SmallLib.b is not supported here",
+        );
+        assert_eq!(d.file, None);
+        assert_eq!(d.span.unwrap().start_col, 21);
+    }
+
+    #[test]
+    fn parse_category() {
+        let d = Diagnostic::parse(
+            Severity::Error,
+            "/repo/Demo.agda:6.1-7: error: [MissingDefinitions]\nThe following names are declared",
+        );
+        assert_eq!(d.file.as_deref(), Some("/repo/Demo.agda"));
+        assert_eq!(d.span.unwrap().end_col, 7);
+        assert_eq!(d.category.as_deref(), Some("MissingDefinitions"));
+        assert_eq!(d.message, "The following names are declared");
+        let w = Diagnostic::parse(
+            Severity::Warning,
+            "/repo/Demo.agda:3.1-5: warning: -W[no]UnreachableClauses\nUnreachable clause",
+        );
+        assert_eq!(w.category.as_deref(), Some("UnreachableClauses"));
+    }
+
+    #[test]
+    fn parse_garbage() {
+        let d = Diagnostic::parse(Severity::Error, "Something went wrong: badly");
+        assert_eq!(d.span, None);
+        assert_eq!(d.message, "Something went wrong: badly");
+    }
+}
@@ -0,0 +1,181 @@
+use std::ops::Range;
+
+use super::{indentation, line_range, EditResult, TextEdit};
+
+fn indent_len(line: &str) -> usize {
+    indentation(line).chars().count()
+}
+
+fn is_where_line(line: &str) -> bool {
+    let line = line.trim_end();
+    line.trim_start() == "where" || line.ends_with(" where")
+}
+
+/// Prefix every line of every clause with `indent`.
+fn indent_clauses(clauses: &[String], indent: &str) -> String {
+    let lines: Vec<String> = clauses
+        .iter()
+        .flat_map(|clause| clause.split('\n'))
+        .map(|line| format!("{}{}", indent, line))
+        .collect();
+    lines.join("\n")
+}
+
+/// A line starting the `where` block of a clause, like `  where` or `  where y = x`.
+fn starts_where(line: &str) -> bool {
+    let line = line.trim_start();
+    line.strip_prefix("where")
+        .is_some_and(|rest| rest.is_empty() || rest.starts_with(char::is_whitespace))
+}
+
+/// The (1-indexed, inclusive) lines of the clause containing `line`:
+/// the first line of a clause is the least indented one,
+/// and everything more indented below belongs to it, up to its `where` block.
+/// Like Emacs, the `where` block is kept, so it ends up after the new clauses.
+fn clause_lines(lines: &[&str], line: usize) -> (usize, usize) {
+    let mut first = line;
+    while first > 1 {
+        let above = lines[first - 2];
+        let continued = !above.trim().is_empty()
+            && indent_len(above) < indent_len(lines[first - 1])
+            && !is_where_line(above);
+        if !continued {
+            break;
+        }
+        first -= 1;
+    }
+    let head_indent = indent_len(lines[first - 1]);
+    let mut last = line;
+    while last < lines.len() {
+        let below = lines[last];
+        if below.trim().is_empty() || indent_len(below) <= head_indent || starts_where(below) {
+            break;
+        }
+        last += 1;
+    }
+    (first, last)
+}
+
+fn replace_lines(
+    source: &str,
+    lines: &[&str],
+    (first, last): (usize, usize),
+    clauses: &[String],
+) -> EditResult {
+    let start = line_range(source, first).ok_or_else(|| format!("No line {}.", first))?;
+    let end = line_range(source, last).ok_or_else(|| format!("No line {}.", last))?;
+    let text = indent_clauses(clauses, indentation(lines[first - 1]));
+    Ok(vec![TextEdit::new(start.start..end.end, text)])
+}
+
+/// Replace the function clause at `line`, which may span several lines.
+pub(super) fn function_clause(source: &str, line: usize, clauses: &[String]) -> EditResult {
+    let lines: Vec<&str> = source.split('\n').collect();
+    if line == 0 || line > lines.len() {
+        return Err(format!("No line {}.", line));
+    }
+    replace_lines(source, &lines, clause_lines(&lines, line), clauses)
+}
+
+/// Where the clause of an extended lambda starts.
+enum Opening {
+    /// `{` or `;`, at this offset.
+    Delimiter(usize),
+    /// `λ where`, the `where` ends at this offset.
+    Where(usize),
+}
+
+fn is_boundary(c: Option<&char>) -> bool {
+    c.is_none_or(|c| c.is_whitespace() || "(){};".contains(*c))
+}
+
+fn word_ends_at(chars: &[char], i: usize, word: &str) -> bool {
+    let len = word.chars().count();
+    if i + 1 < len {
+        return false;
+    }
+    let start = i + 1 - len;
+    chars[start..=i].iter().copied().eq(word.chars())
+        && is_boundary(start.checked_sub(1).and_then(|s| chars.get(s)))
+        && is_boundary(chars.get(i + 1))
+}
+
+fn find_opening(chars: &[char], goal_start: usize) -> Option<Opening> {
+    let mut depth = 0;
+    for i in (0..goal_start).rev() {
+        match chars[i] {
+            ')' | ']' | '}' => depth += 1,
+            '(' | '[' if depth == 0 => return None,
+            '(' | '[' => depth -= 1,
+            '{' | ';' if depth == 0 => return Some(Opening::Delimiter(i)),
+            '{' => depth -= 1,
+            _ if depth == 0 && word_ends_at(chars, i, "where") => return Some(Opening::Where(i)),
+            _ => {}
+        }
+    }
+    None
+}
+
+fn find_closing(chars: &[char], goal_end: usize) -> Option<usize> {
+    let mut depth = 0;
+    for (j, c) in chars.iter().enumerate().skip(goal_end) {
+        match c {
+            '(' | '[' | '{' => depth += 1,
+            '}' | ';' if depth == 0 => return Some(j),
+            ')' | ']' | '}' => depth -= 1,
+            _ => {}
+        }
+    }
+    None
+}
+
+/// The whitespace before `chars[i]` if it's the first thing on its line.
+fn leading_indent(chars: &[char], i: usize) -> Option<String> {
+    let line_start = chars[..i]
+        .iter()
+        .rposition(|&c| c == '\n')
+        .map_or(0, |p| p + 1);
+    let before = &chars[line_start..i];
+    if before.iter().all(|c| c.is_whitespace()) {
+        Some(before.iter().collect())
+    } else {
+        None
+    }
+}
+
+/// Replace the clause of a pattern-matching lambda containing the goal,
+/// either `λ { p → e ; ... }` (on one or several lines) or `λ where` (layout).
+pub(super) fn lambda_clause(source: &str, goal: Range<usize>, clauses: &[String]) -> EditResult {
+    let not_found = || "Cannot find the extended lambda around the goal.".to_owned();
+    let chars: Vec<char> = source.chars().collect();
+    match find_opening(&chars, goal.start).ok_or_else(not_found)? {
+        Opening::Delimiter(open) => {
+            let close = find_closing(&chars, goal.end).ok_or_else(not_found)?;
+            // Don't touch the whitespace around the clause.
+            let inner = &chars[open + 1..close];
+            let skip_start = inner.iter().take_while(|c| c.is_whitespace()).count();
+            let skip_end = inner.iter().rev().take_while(|c| c.is_whitespace()).count();
+            let range = open + 1 + skip_start..close - skip_end;
+            let multi_line = |i: usize| Some(i).filter(|&i| chars[i] == ';');
+            let indent = multi_line(open)
+                .and_then(|i| leading_indent(&chars, i))
+                .or_else(|| multi_line(close).and_then(|i| leading_indent(&chars, i)));
+            let separator = match indent {
+                Some(indent) => format!("\n{}; ", indent),
+                None => " ; ".to_owned(),
+            };
+            Ok(vec![TextEdit::new(range, clauses.join(&separator))])
+        }
+        Opening::Where(where_end) => {
+            let lines: Vec<&str> = source.split('\n').collect();
+            let line_of =
+                |offset: usize| chars[..offset].iter().filter(|&&c| c == '\n').count() + 1;
+            let where_line = line_of(where_end);
+            let goal_line = line_of(goal.start);
+            if goal_line == where_line {
+                return Err("Put the clauses of `λ where` on their own lines.".to_owned());
+            }
+            replace_lines(source, &lines, clause_lines(&lines, goal_line), clauses)
+        }
+    }
+}
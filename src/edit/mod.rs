@@ -0,0 +1,457 @@
+use std::ops::Range;
+
+use either::Either;
+
+use crate::pos::{InteractionId, InteractionPoint, Interval, Pos};
+use crate::resp::{GiveAction, MakeCase, MakeCaseVariant, OneSolution};
+
+use self::clause::{function_clause, lambda_clause};
+
+/// Finding the clause to replace when splitting.
+mod clause;
+
+/// Replace the characters in `range` with `text`.
+/// Offsets are 0-indexed and count characters (not bytes),
+/// while Agda's [`Pos::pos`](crate::pos::Pos::pos) is 1-indexed.
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct TextEdit {
+    pub range: Range<usize>,
+    pub text: String,
+}
+
+impl TextEdit {
+    pub fn new(range: Range<usize>, text: String) -> Self {
+        TextEdit { range, text }
+    }
+
+    pub fn insert(at: usize, text: String) -> Self {
+        Self::new(at..at, text)
+    }
+
+    /// How much the text after this edit moves.
+    pub fn delta(&self) -> isize {
+        self.text.chars().count() as isize - self.range.len() as isize
+    }
+}
+
+/// Either the edits, or why they can't be computed.
+pub type EditResult<T = Vec<TextEdit>> = Result<T, String>;
+
+/// The character range of an interval.
+pub fn interval_range(interval: &Interval) -> Range<usize> {
+    interval.range_shift_left(1)
+}
+
+fn goal_interval(ip: &InteractionPoint) -> EditResult<&Interval> {
+    ip.range
+        .first()
+        .ok_or_else(|| format!("Goal ?{} has no range.", ip.id))
+}
+
+/// The character range of the `line`-th (1-indexed) line, without the line break.
+pub fn line_range(source: &str, line: usize) -> Option<Range<usize>> {
+    let mut start = 0;
+    for (i, text) in source.split('\n').enumerate() {
+        let len = text.chars().count();
+        if i + 1 == line {
+            return Some(start..start + len);
+        }
+        start += len + 1;
+    }
+    None
+}
+
+/// The leading whitespace of a line.
+pub fn indentation(line: &str) -> &str {
+    &line[..line.len() - line.trim_start().len()]
+}
+
+fn char_slice(source: &str, range: Range<usize>) -> &str {
+    let byte = |i| {
+        source
+            .char_indices()
+            .nth(i)
+            .map_or(source.len(), |(b, _)| b)
+    };
+    &source[byte(range.start)..byte(range.end)]
+}
+
+/// Wrap `expr` in parentheses, unless it's obviously atomic.
+pub fn parenthesize(expr: &str) -> String {
+    let expr = expr.trim();
+    let atomic =
+        !expr.contains(char::is_whitespace) || enclosed(expr, '(', ')') || enclosed(expr, '{', '}');
+    if atomic {
+        expr.to_owned()
+    } else {
+        format!("({})", expr)
+    }
+}
+
+/// Is the first bracket of `expr` closed by its last character?
+/// So `(f x)` is, but `(f x) (g y)` isn't.
+fn enclosed(expr: &str, open: char, close: char) -> bool {
+    if !expr.starts_with(open) || !expr.ends_with(close) {
+        return false;
+    }
+    let mut depth = 0usize;
+    let last = expr.chars().count() - 1;
+    for (i, c) in expr.chars().enumerate() {
+        if c == open {
+            depth += 1;
+        } else if c == close {
+            depth -= 1;
+            if depth == 0 {
+                return i == last;
+            }
+        }
+    }
+    false
+}
+
+/// The text inside a goal, without the `{! !}` markers.
+pub fn goal_content(source: &str, ip: &InteractionPoint) -> EditResult<String> {
+    let interval = goal_interval(ip)?;
+    let text = char_slice(source, interval_range(interval)).trim();
+    let text = match text.strip_prefix("{!") {
+        Some(inner) => inner.strip_suffix("!}").unwrap_or(inner),
+        None => text.trim_start_matches('?'),
+    };
+    Ok(text.trim().to_owned())
+}
+
+/// Replace the goal with the string Agda gives back,
+/// or with `given` (the expression that was given) when Agda says to keep it,
+/// parenthesized if Agda asks to.
+///
+/// Use [`goal_content`](self::goal_content) for `given`
+/// if the expression was typed into the goal.
+pub fn give(action: &GiveAction, given: &str) -> EditResult {
+    let interval = goal_interval(&action.interaction_point)?;
+    let text = match action.give_result.clone().into_either() {
+        Either::Left(s) => s,
+        Either::Right(true) => format!("({})", given.trim()),
+        Either::Right(false) => given.trim().to_owned(),
+    };
+    Ok(vec![TextEdit::new(interval_range(interval), text)])
+}
+
+/// Replace the clause containing the goal with the new clauses,
+/// with the indentation of the original clause.
+/// Function clauses may span several lines (including a `where` block),
+/// extended lambdas may use braces or `λ where`.
+pub fn make_case(source: &str, make_case: &MakeCase) -> EditResult {
+    let interval = goal_interval(&make_case.interaction_point)?;
+    let clauses = &make_case.clauses;
+    match make_case.variant {
+        MakeCaseVariant::Function => function_clause(source, interval.start.line, clauses),
+        MakeCaseVariant::ExtendedLambda => lambda_clause(source, interval_range(interval), clauses),
+    }
+}
+
+/// Fill the solved goals with their solutions, parenthesized when needed.
+pub fn solve_all(solutions: &[OneSolution]) -> EditResult {
+    let mut edits = Vec::with_capacity(solutions.len());
+    for solution in solutions {
+        let interval = goal_interval(&solution.interaction_point)?;
+        let text = parenthesize(&solution.expression);
+        edits.push(TextEdit::new(interval_range(interval), text));
+    }
+    edits.sort_by_key(|e| e.range.start);
+    Ok(edits)
+}
+
+/// The first line of the top-level definition containing `line` (1-indexed),
+/// which includes its type signature.
+pub fn definition_start(source: &str, line: usize) -> usize {
+    let lines: Vec<&str> = source.split('\n').collect();
+    let is_top = |l: &str| !l.trim().is_empty() && indentation(l).is_empty();
+    let first_word = |l: &str| l.split_whitespace().next().unwrap_or_default().to_owned();
+    let mut current = line.min(lines.len()).max(1);
+    // Find the top-level line of the current clause.
+    while current > 1 && !is_top(lines[current - 1]) {
+        current -= 1;
+    }
+    let name = first_word(lines[current - 1]);
+    // Then the other clauses and the signature of the same name.
+    while current > 1 {
+        let prev = lines[current - 2];
+        if prev.trim().is_empty() || (is_top(prev) && first_word(prev) != name) {
+            break;
+        }
+        current -= 1;
+    }
+    current
+}
+
+/// Insert a helper function (the signature from
+/// [`GoalInfo::HelperFunction`](crate::resp::GoalInfo::HelperFunction) and a clause
+/// with a hole) above the definition containing the goal.
+pub fn helper_function(source: &str, ip: &InteractionPoint, signature: &str) -> EditResult {
+    let interval = goal_interval(ip)?;
+    let name = signature
+        .split_whitespace()
+        .next()
+        .ok_or_else(|| "The signature is empty.".to_owned())?;
+    let line = definition_start(source, interval.start.line);
+    let at = line_range(source, line).map_or(0, |r| r.start);
+    let text = format!("{}\n{} = ?\n\n", signature.trim(), name);
+    Ok(vec![TextEdit::insert(at, text)])
+}
+
+/// Apply non-overlapping edits.
+pub fn apply(source: &str, edits: &[TextEdit]) -> String {
+    let mut sorted: Vec<&TextEdit> = edits.iter().collect();
+    sorted.sort_by_key(|e| e.range.start);
+    let mut result = String::with_capacity(source.len());
+    let mut chars = source.chars();
+    let mut offset = 0;
+    for edit in sorted {
+        result.extend(chars.by_ref().take(edit.range.start - offset));
+        chars.by_ref().take(edit.range.len()).for_each(drop);
+        result.push_str(&edit.text);
+        offset = edit.range.end;
+    }
+    result.extend(chars);
+    result
+}
+
+/// The position of a character offset in `source`.
+pub fn pos_of(source: &str, offset: usize) -> Pos {
+    let mut line = 1;
+    let mut col = 1;
+    for c in source.chars().take(offset) {
+        if c == '\n' {
+            line += 1;
+            col = 1;
+        } else {
+            col += 1;
+        }
+    }
+    Pos {
+        pos: offset + 1,
+        line,
+        col,
+    }
+}
+
+fn shift_offset(offset: usize, edits: &[TextEdit]) -> usize {
+    let delta: isize = edits
+        .iter()
+        .filter(|e| e.range.end <= offset)
+        .map(TextEdit::delta)
+        .sum();
+    (offset as isize + delta) as usize
+}
+
+/// Move the interaction points after `edits` were applied,
+/// resulting in `new_source`. Goals replaced by an edit are removed.
+pub fn shift_interaction_points(
+    points: &mut Vec<InteractionPoint>,
+    edits: &[TextEdit],
+    new_source: &str,
+) {
+    let overlaps = |e: &TextEdit, range: &Range<usize>| {
+        // Also covers insertions strictly inside the goal.
+        e.range.start < range.end && range.start < e.range.end
+    };
+    points.retain(|ip| {
+        ip.range.iter().all(|i| {
+            let range = interval_range(i);
+            !edits.iter().any(|e| overlaps(e, &range))
+        })
+    });
+    for ip in points.iter_mut() {
+        for interval in ip.range.iter_mut() {
+            let range = interval_range(interval);
+            interval.start = pos_of(new_source, shift_offset(range.start, edits));
+            interval.end = pos_of(new_source, shift_offset(range.end, edits));
+        }
+    }
+}
+
+/// Take the goals Agda still has from `agda_points`, keeping the shifted ranges
+/// in `points`: Agda's ranges are from before the edits, until the next load.
+pub fn retain_goals(points: &mut Vec<InteractionPoint>, agda_points: &[InteractionPoint]) {
+    points.retain(|ip| agda_points.iter().any(|p| p.id == ip.id));
+}
+
+/// The goal with the given id.
+pub fn find_goal(points: &[InteractionPoint], id: InteractionId) -> Option<&InteractionPoint> {
+    points.iter().find(|ip| ip.id == id)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::resp::MakeCaseVariant;
+
+    const SOURCE: &str = "module A where\n\nf : Nat → Nat\nf x = {! !}\n\ng : Nat\ng = ?\n";
+
+    /// `start` and `end` are byte offsets.
+    fn ip(id: InteractionId, source: &str, start: usize, end: usize) -> InteractionPoint {
+        let start = source[..start].chars().count();
+        let end = source[..end].chars().count();
+        let interval = Interval {
+            file: None,
+            start: pos_of(source, start),
+            end: pos_of(source, end),
+        };
+        InteractionPoint {
+            id,
+            range: vec![interval],
+        }
+    }
+
+    fn goals() -> Vec<InteractionPoint> {
+        let hole = SOURCE.find("{! !}").unwrap();
+        let q = SOURCE.rfind('?').unwrap();
+        vec![ip(0, SOURCE, hole, hole + 5), ip(1, SOURCE, q, q + 1)]
+    }
+
+    #[test]
+    fn give_string() {
+        let action = GiveAction {
+            give_result: serde_json::from_str(r#"{"str":"suc x"}"#).unwrap(),
+            interaction_point: goals()[0].clone(),
+        };
+        let edits = give(&action, "x").unwrap();
+        let new = apply(SOURCE, &edits);
+        assert!(new.contains("\nf x = suc x\n"));
+        let mut points = goals();
+        shift_interaction_points(&mut points, &edits, &new);
+        assert_eq!(points.len(), 1);
+        let range = interval_range(points[0].the_interval());
+        assert_eq!(char_slice(&new, range), "?");
+        assert_eq!(points[0].the_interval().start.line, 7);
+    }
+
+    #[test]
+    fn give_multi_line() {
+        let action = GiveAction {
+            give_result: serde_json::from_str(r#"{"str":"λ where\n  zero → 0\n  (suc n) → n"}"#)
+                .unwrap(),
+            interaction_point: goals()[0].clone(),
+        };
+        let edits = give(&action, "").unwrap();
+        let new = apply(SOURCE, &edits);
+        let mut points = goals();
+        shift_interaction_points(&mut points, &edits, &new);
+        // Agda's goals after the give, with the ranges before it.
+        retain_goals(&mut points, &goals()[1..]);
+        assert_eq!(points.len(), 1);
+        assert_eq!(points[0].id, 1);
+        let range = interval_range(points[0].the_interval());
+        assert_eq!(char_slice(&new, range), "?");
+        assert_eq!(points[0].the_interval().start.line, 9);
+    }
+
+    #[test]
+    fn give_paren() {
+        let typed = SOURCE.replace("{! !}", "{! suc x !}");
+        let (start, end) = (typed.find("{!").unwrap(), typed.find("!}").unwrap() + 2);
+        assert_eq!(
+            goal_content(&typed, &ip(0, &typed, start, end)).unwrap(),
+            "suc x"
+        );
+        let point = goals()[0].clone();
+        let paren = GiveAction {
+            give_result: serde_json::from_str(r#"{"paren":true}"#).unwrap(),
+            interaction_point: point.clone(),
+        };
+        let new = apply(SOURCE, &give(&paren, "suc x").unwrap());
+        assert!(new.contains("\nf x = (suc x)\n"));
+        let no_paren = GiveAction {
+            give_result: serde_json::from_str(r#"{"paren":false}"#).unwrap(),
+            interaction_point: point,
+        };
+        let new = apply(SOURCE, &give(&no_paren, " suc x ").unwrap());
+        assert!(new.contains("\nf x = suc x\n"));
+    }
+
+    #[test]
+    fn make_case_function() {
+        let mk = MakeCase {
+            variant: MakeCaseVariant::Function,
+            interaction_point: goals()[0].clone(),
+            clauses: vec!["f zero = ?".to_owned(), "f (suc x) = ?".to_owned()],
+        };
+        let new = apply(SOURCE, &make_case(SOURCE, &mk).unwrap());
+        assert!(new.contains("\nf zero = ?\nf (suc x) = ?\n\ng"));
+    }
+
+    fn split(source: &str, hole: &str, variant: MakeCaseVariant, clauses: &[&str]) -> String {
+        let start = source.find(hole).unwrap();
+        let mk = MakeCase {
+            variant,
+            interaction_point: ip(0, source, start, start + hole.len()),
+            clauses: clauses.iter().map(|c| c.to_string()).collect(),
+        };
+        apply(source, &make_case(source, &mk).unwrap())
+    }
+
+    #[test]
+    fn make_case_multi_line() {
+        let source = "f : Nat → Nat\nf x = go x\n  where\n    go : Nat → Nat\n    go y =\n      {! !}\n\ng = f\n";
+        let clauses = ["go zero = ?", "go (suc y) = ?"];
+        let new = split(source, "{! !}", MakeCaseVariant::Function, &clauses);
+        assert!(new.contains("    go : Nat → Nat\n    go zero = ?\n    go (suc y) = ?\n\ng = f"));
+        let clauses_f = ["f zero = ?", "f (suc x) = ?"];
+        let source = "f : Nat → Nat\nf x =\n  {! !}\n  where y = x\ng = f\n";
+        let new = split(source, "{! !}", MakeCaseVariant::Function, &clauses_f);
+        assert_eq!(
+            new,
+            "f : Nat → Nat\nf zero = ?\nf (suc x) = ?\n  where y = x\ng = f\n"
+        );
+        let source = "f : Nat → Nat\nf x =\n  {! !}\n  where\n    y = x\ng = f\n";
+        let new = split(source, "{! !}", MakeCaseVariant::Function, &clauses_f);
+        assert_eq!(
+            new,
+            "f : Nat → Nat\nf zero = ?\nf (suc x) = ?\n  where\n    y = x\ng = f\n"
+        );
+    }
+
+    #[test]
+    fn make_case_lambda() {
+        let lambda = MakeCaseVariant::ExtendedLambda;
+        let clauses = ["zero → ?", "(suc n) → ?"];
+        let source = "f = λ { x → {! !} }\n";
+        let new = split(source, "{! !}", lambda, &clauses);
+        assert_eq!(new, "f = λ { zero → ? ; (suc n) → ? }\n");
+        let source = "f = λ { true → ?\n      ; x → {! !}\n      }\n";
+        let new = split(source, "{! !}", lambda, &clauses);
+        assert_eq!(
+            new,
+            "f = λ { true → ?\n      ; zero → ?\n      ; (suc n) → ?\n      }\n"
+        );
+        let source = "f = λ where\n  x → {! !}\ng = f\n";
+        let new = split(source, "{! !}", lambda, &clauses);
+        assert_eq!(new, "f = λ where\n  zero → ?\n  (suc n) → ?\ng = f\n");
+    }
+
+    #[test]
+    fn solve() {
+        let solutions = vec![OneSolution {
+            interaction_point: goals()[1].clone(),
+            expression: "suc zero".to_owned(),
+        }];
+        let new = apply(SOURCE, &solve_all(&solutions).unwrap());
+        assert!(new.ends_with("g = (suc zero)\n"));
+    }
+
+    #[test]
+    fn parens() {
+        assert_eq!(parenthesize("x"), "x");
+        assert_eq!(parenthesize("(f x)"), "(f x)");
+        assert_eq!(parenthesize("((f x) (g y))"), "((f x) (g y))");
+        assert_eq!(parenthesize("(f x) (g y)"), "((f x) (g y))");
+        assert_eq!(parenthesize("{x} → {y}"), "({x} → {y})");
+    }
+
+    #[test]
+    fn helper() {
+        let edits = helper_function(SOURCE, &goals()[0], "aux : Nat → Nat").unwrap();
+        let new = apply(SOURCE, &edits);
+        assert!(new.contains("\n\naux : Nat → Nat\naux = ?\n\nf : Nat → Nat\n"));
+    }
+}
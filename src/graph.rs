@@ -0,0 +1,257 @@
+use std::collections::{BTreeMap, BTreeSet};
+use std::fmt::Write;
+use std::fs::read_to_string;
+use std::io;
+use std::time::{Duration, Instant};
+
+use serde::{Serialize, Serializer};
+
+use crate::resp::Progress;
+
+/// One module in the [`ModuleGraph`](self::ModuleGraph).
+#[derive(Serialize, Clone, Default, Debug, Eq, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct ModuleNode {
+    /// Source file, or interface file for modules that are not re-checked.
+    pub path: Option<String>,
+    pub imports: BTreeSet<String>,
+    /// Was it type-checked (instead of loaded from the interface file) in the last load?
+    pub checked: bool,
+    /// Time between `Checking` and `Finished`, including the imported modules checked in between.
+    #[serde(serialize_with = "millis", rename = "checkMillis")]
+    pub check_time: Option<Duration>,
+    /// [`check_time`](Self::check_time) without the imported modules.
+    #[serde(serialize_with = "millis", rename = "selfMillis")]
+    pub self_time: Option<Duration>,
+}
+
+fn millis<S: Serializer>(d: &Option<Duration>, s: S) -> Result<S::Ok, S::Error> {
+    d.map(|d| d.as_millis() as u64).serialize(s)
+}
+
+#[derive(Clone, Debug)]
+struct Frame {
+    module: String,
+    start: Option<Instant>,
+    children: Duration,
+}
+
+/// Module dependency graph, built from the loading progress
+/// and the `import` lines of the source files.
+#[derive(Serialize, Clone, Default, Debug)]
+pub struct ModuleGraph {
+    pub modules: BTreeMap<String, ModuleNode>,
+    /// The chain of imports being loaded, indexed by depth.
+    #[serde(skip)]
+    stack: Vec<Frame>,
+}
+
+impl ModuleGraph {
+    /// Forget the timing of the previous load, keep the modules.
+    pub fn begin_load(&mut self) {
+        self.stack.clear();
+        for node in self.modules.values_mut() {
+            node.checked = false;
+            node.check_time = None;
+            node.self_time = None;
+        }
+    }
+
+    pub fn record(&mut self, progress: &Progress) {
+        self.record_at(progress, Instant::now())
+    }
+
+    pub fn record_at(&mut self, progress: &Progress, now: Instant) {
+        use Progress::*;
+        match progress {
+            Checking {
+                module,
+                path,
+                depth,
+            } => {
+                self.enter(module, path, *depth, Some(now));
+                self.node(module).checked = true;
+            }
+            Loading {
+                module,
+                path,
+                depth,
+            }
+            | Skipping {
+                module,
+                path,
+                depth,
+            } => self.enter(module, path, *depth, None),
+            Finished { module, .. } => self.finish(module, now),
+            Message(_) | Clear => {}
+        }
+    }
+
+    fn node(&mut self, module: &str) -> &mut ModuleNode {
+        self.modules.entry(module.to_owned()).or_default()
+    }
+
+    fn enter(&mut self, module: &str, path: &Option<String>, depth: usize, start: Option<Instant>) {
+        self.stack.truncate(depth);
+        if let Some(parent) = self.stack.last() {
+            let parent = parent.module.clone();
+            self.node(&parent).imports.insert(module.to_owned());
+        }
+        let node = self.node(module);
+        if path.is_some() {
+            node.path = path.clone();
+        }
+        self.stack.push(Frame {
+            module: module.to_owned(),
+            start,
+            children: Duration::default(),
+        });
+    }
+
+    fn finish(&mut self, module: &str, now: Instant) {
+        let idx = match self.stack.iter().rposition(|f| f.module == module) {
+            Some(idx) => idx,
+            None => return,
+        };
+        let frame = self.stack.remove(idx);
+        self.stack.truncate(idx);
+        let start = match frame.start {
+            Some(start) => start,
+            None => return,
+        };
+        let total = now.duration_since(start);
+        if let Some(parent) = self.stack.last_mut() {
+            parent.children += total;
+        }
+        let node = self.node(module);
+        node.check_time = Some(total);
+        node.self_time = Some(total.saturating_sub(frame.children));
+    }
+
+    /// Add the imports written in `source` to `module`.
+    pub fn add_source_imports(&mut self, module: &str, source: &str) {
+        let imports = imports_of(source);
+        self.node(module).imports.extend(imports);
+    }
+
+    /// Read the source files of the checked modules and add their imports,
+    /// which also covers the modules loaded from interface files.
+    pub fn scan_sources(&mut self) -> io::Result<()> {
+        let sources: Vec<(String, String)> = self
+            .modules
+            .iter()
+            .filter_map(|(m, n)| Some((m.clone(), n.path.clone()?)))
+            .filter(|(_, p)| !p.ends_with(".agdai"))
+            .collect();
+        for (module, path) in sources {
+            let source = read_to_string(path)?;
+            self.add_source_imports(&module, &source);
+        }
+        Ok(())
+    }
+
+    /// Checked modules, the slowest (by [`self_time`](ModuleNode::self_time)) first.
+    pub fn slowest(&self) -> Vec<(&str, Duration)> {
+        let mut times: Vec<_> = self
+            .modules
+            .iter()
+            .filter_map(|(m, n)| Some((m.as_str(), n.self_time?)))
+            .collect();
+        times.sort_by(|(_, a), (_, b)| b.cmp(a));
+        times
+    }
+
+    pub fn to_json(&self) -> serde_json::Result<String> {
+        serde_json::to_string_pretty(self)
+    }
+
+    /// Graphviz representation, labelled with the checking time.
+    pub fn to_dot(&self) -> String {
+        let mut dot = String::from("digraph modules {\n");
+        for (module, node) in &self.modules {
+            let label = match node.self_time {
+                Some(t) => format!("{}\\n{}ms", module, t.as_millis()),
+                None => module.clone(),
+            };
+            let style = if node.checked { "bold" } else { "solid" };
+            writeln!(
+                dot,
+                "  {:?} [label=\"{}\", style={}];",
+                module, label, style
+            )
+            .unwrap();
+        }
+        for (module, node) in &self.modules {
+            for import in &node.imports {
+                writeln!(dot, "  {:?} -> {:?};", module, import).unwrap();
+            }
+        }
+        dot.push_str("}\n");
+        dot
+    }
+}
+
+/// Modules imported by `import M` or `open import M` lines.
+pub fn imports_of(source: &str) -> Vec<String> {
+    source
+        .lines()
+        .filter_map(|line| {
+            let line = line.trim_start();
+            let line = line.strip_prefix("open ").unwrap_or(line).trim_start();
+            let rest = line.strip_prefix("import ")?;
+            let module = rest.split_whitespace().next()?;
+            Some(module.trim_end_matches(';').to_owned())
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn checking(module: &str, depth: usize) -> Progress {
+        let path = Some(format!("/src/{}.agda", module));
+        let module = module.to_owned();
+        Progress::Checking {
+            module,
+            path,
+            depth,
+        }
+    }
+
+    fn finished(module: &str, depth: usize) -> Progress {
+        let module = module.to_owned();
+        Progress::Finished { module, depth }
+    }
+
+    #[test]
+    fn graph_from_progress() {
+        let mut graph = ModuleGraph::default();
+        let t = Instant::now();
+        let ms = |n| t + Duration::from_millis(n);
+        graph.record_at(&checking("A", 0), ms(0));
+        graph.record_at(&checking("B", 1), ms(10));
+        graph.record_at(&finished("B", 1), ms(40));
+        let loading = Progress::Loading {
+            module: "C".to_owned(),
+            path: Some("/src/C.agdai".to_owned()),
+            depth: 1,
+        };
+        graph.record_at(&loading, ms(40));
+        graph.record_at(&finished("A", 0), ms(50));
+        let a = &graph.modules["A"];
+        assert!(a.imports.contains("B") && a.imports.contains("C"));
+        assert_eq!(a.check_time, Some(Duration::from_millis(50)));
+        assert_eq!(a.self_time, Some(Duration::from_millis(20)));
+        assert!(!graph.modules["C"].checked);
+        assert_eq!(graph.slowest()[0].0, "B");
+        assert!(graph.to_dot().contains("\"A\" -> \"B\";"));
+        assert!(graph.to_json().unwrap().contains("\"selfMillis\": 30"));
+    }
+
+    #[test]
+    fn source_imports() {
+        let source = "module A where\n\nopen import Data.Nat using (ℕ)\nimport Data.List as L\n-- import Nope\n";
+        assert_eq!(imports_of(source), vec!["Data.Nat", "Data.List"]);
+    }
+}
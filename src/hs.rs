@@ -16,9 +16,9 @@ impl From<bool> for HaskellBool {
     }
 }
 
-impl Into<bool> for HaskellBool {
-    fn into(self) -> bool {
-        match self {
+impl From<HaskellBool> for bool {
+    fn from(b: HaskellBool) -> Self {
+        match b {
             HaskellBool::True => true,
             HaskellBool::False => false,
         }
@@ -45,6 +45,18 @@ pub mod pos;
 /// Response data types (output of Agda).
 pub mod resp;
 
+/// Structured diagnostics, parsed from Agda's error and warning messages.
+pub mod diag;
+
+/// Module dependency graph, built from the loading progress.
+pub mod graph;
+
+/// Apply Agda's answers (give, case split, solutions) to the source text.
+pub mod edit;
+
+/// Agda libraries (`.agda-lib` files), include paths and module names.
+pub mod library;
+
 /// Agda commands (input to Agda).
 pub mod cmd;
 
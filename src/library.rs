@@ -0,0 +1,426 @@
+use std::collections::BTreeSet;
+use std::env;
+use std::fs::{read_dir, read_to_string};
+use std::io;
+use std::path::{Path, PathBuf};
+
+/// Extensions of Agda source files, the literate ones included.
+pub const SOURCE_EXTENSIONS: &[&str] = &[
+    ".agda",
+    ".lagda",
+    ".lagda.md",
+    ".lagda.tex",
+    ".lagda.rst",
+    ".lagda.org",
+    ".lagda.typ",
+    ".lagda.tree",
+];
+
+/// An `.agda-lib` file.
+#[derive(Debug, Clone, Default, Eq, PartialEq)]
+pub struct AgdaLib {
+    /// The `.agda-lib` file itself.
+    pub path: PathBuf,
+    pub name: Option<String>,
+    /// Include directories, relative to the library root.
+    pub include: Vec<PathBuf>,
+    /// Names of the libraries it depends on.
+    pub depend: Vec<String>,
+    /// Default flags of the files in the library.
+    pub flags: Vec<String>,
+}
+
+/// Like Agda, a comment is `--` followed by a space, so flags are not comments.
+fn strip_comment(line: &str) -> &str {
+    let comment = line.match_indices("--").find(|(i, _)| {
+        let after = line[i + 2..].chars().next();
+        after.is_some_and(char::is_whitespace)
+    });
+    match comment {
+        Some((i, _)) => &line[..i],
+        None => line,
+    }
+}
+
+impl AgdaLib {
+    /// Parse the contents of the `.agda-lib` file at `path`.
+    /// A field starts at the beginning of a line, like `include: src`,
+    /// and goes on in the indented lines below.
+    pub fn parse(path: PathBuf, text: &str) -> Result<Self, String> {
+        let mut lib = AgdaLib {
+            path,
+            ..Default::default()
+        };
+        let mut field = None;
+        for (i, line) in text.lines().enumerate() {
+            let line = strip_comment(line);
+            if line.trim().is_empty() {
+                continue;
+            }
+            let values = if line.starts_with(char::is_whitespace) {
+                match field {
+                    Some(_) => line,
+                    None => return Err(format!("Line {}: value without a field.", i + 1)),
+                }
+            } else {
+                let colon = line
+                    .find(':')
+                    .ok_or_else(|| format!("Line {}: expected `field: value`.", i + 1))?;
+                field = Some(line[..colon].trim().to_owned());
+                &line[colon + 1..]
+            };
+            let mut values = values.split_whitespace().map(str::to_owned);
+            match field.as_deref() {
+                Some("name") => lib.name = values.next_back().or(lib.name.take()),
+                Some("include") => lib.include.extend(values.map(PathBuf::from)),
+                Some("depend") => {
+                    // Dependencies can also be separated by commas.
+                    for value in values {
+                        let names = value.split(',').filter(|name| !name.is_empty());
+                        lib.depend.extend(names.map(str::to_owned));
+                    }
+                }
+                Some("flags") => lib.flags.extend(values),
+                // Fields of newer Agda versions.
+                _ => {}
+            }
+        }
+        Ok(lib)
+    }
+
+    pub fn read(path: PathBuf) -> io::Result<Result<Self, String>> {
+        let text = read_to_string(&path)?;
+        Ok(Self::parse(path, &text))
+    }
+
+    /// The nearest `.agda-lib` file in the directories containing `file`.
+    pub fn find(file: &Path) -> io::Result<Option<PathBuf>> {
+        for dir in file.ancestors().skip(1) {
+            if !dir.is_dir() {
+                continue;
+            }
+            let mut libs: Vec<PathBuf> = read_dir(dir)?
+                .filter_map(|entry| entry.ok().map(|entry| entry.path()))
+                .filter(|path| path.extension().is_some_and(|ext| ext == "agda-lib"))
+                .collect();
+            libs.sort();
+            if let Some(lib) = libs.into_iter().next() {
+                return Ok(Some(lib));
+            }
+        }
+        Ok(None)
+    }
+
+    /// The directory of the `.agda-lib` file.
+    pub fn root(&self) -> &Path {
+        self.path.parent().unwrap_or_else(|| Path::new("."))
+    }
+
+    /// The include directories, the root if there's none.
+    pub fn include_paths(&self) -> Vec<PathBuf> {
+        if self.include.is_empty() {
+            return vec![self.root().to_owned()];
+        }
+        let paths = self.include.iter().map(|dir| self.root().join(dir));
+        paths.collect()
+    }
+
+    /// Is this the library `name`? The version can be omitted,
+    /// like `standard-library` for `standard-library-2.0`.
+    pub fn is_named(&self, name: &str) -> bool {
+        let own = match &self.name {
+            Some(own) => own,
+            None => return false,
+        };
+        own == name
+            || own.strip_prefix(name).is_some_and(|version| {
+                let version = version.strip_prefix('-').unwrap_or("x");
+                version.chars().all(|c| c.is_ascii_digit() || c == '.')
+            })
+    }
+}
+
+/// Reserved words, which cannot be (parts of) module names.
+const KEYWORDS: &[&str] = &[
+    "abstract",
+    "codata",
+    "coinductive",
+    "constructor",
+    "data",
+    "do",
+    "eta-equality",
+    "field",
+    "forall",
+    "hiding",
+    "import",
+    "in",
+    "inductive",
+    "infix",
+    "infixl",
+    "infixr",
+    "instance",
+    "interleaved",
+    "let",
+    "macro",
+    "module",
+    "mutual",
+    "no-eta-equality",
+    "opaque",
+    "open",
+    "overlap",
+    "pattern",
+    "postulate",
+    "primitive",
+    "private",
+    "public",
+    "quote",
+    "quoteTerm",
+    "record",
+    "renaming",
+    "rewrite",
+    "syntax",
+    "tactic",
+    "to",
+    "unfolding",
+    "unquote",
+    "unquoteDecl",
+    "unquoteDef",
+    "using",
+    "variable",
+    "where",
+    "with",
+    "Set",
+    "Prop",
+    "=",
+    "|",
+    "->",
+    "→",
+    ":",
+    "?",
+    "\\",
+    "λ",
+    "∀",
+    "..",
+    "...",
+    "_",
+];
+
+/// Check `name` (like `Data.Nat`) against Agda's lexical rules for names.
+pub fn check_module_name(name: &str) -> Result<(), String> {
+    let invalid = |why: &str| Err(format!("`{}` is not a valid module name: {}.", name, why));
+    if name.is_empty() {
+        return invalid("it's empty");
+    }
+    for part in name.split('.') {
+        if part.is_empty() {
+            return invalid("it has an empty part");
+        }
+        if let Some(c) = part
+            .chars()
+            .find(|&c| c.is_whitespace() || c.is_control() || ";{}()@\"".contains(c))
+        {
+            return invalid(&format!("`{}` is not allowed", c.escape_default()));
+        }
+        if KEYWORDS.contains(&part) {
+            return invalid(&format!("`{}` is a keyword", part));
+        }
+        if part.chars().all(|c| c.is_ascii_digit()) {
+            return invalid(&format!("`{}` is a number", part));
+        }
+        if part.starts_with('\'') || part.starts_with("--") || part.contains("{-") {
+            return invalid(&format!("`{}` is not a name", part));
+        }
+    }
+    Ok(())
+}
+
+/// The module defined in `file`, if it's in one of the include directories.
+/// With nested include directories, the innermost one is used.
+pub fn module_name(include_paths: &[PathBuf], file: &Path) -> Option<String> {
+    let relative = include_paths
+        .iter()
+        .filter_map(|dir| file.strip_prefix(dir).ok())
+        .min_by_key(|relative| relative.components().count())?;
+    let mut parts: Vec<String> = relative
+        .iter()
+        .map(|part| part.to_str().map(str::to_owned))
+        .collect::<Option<_>>()?;
+    let last = parts.pop()?;
+    let base = SOURCE_EXTENSIONS
+        .iter()
+        .rev()
+        .find_map(|ext| last.strip_suffix(ext))?;
+    parts.push(base.to_owned());
+    Some(parts.join("."))
+}
+
+/// Paths in the `libraries` file, or library names in the `defaults` file.
+fn entries(text: &str) -> impl Iterator<Item = &str> {
+    text.lines()
+        .map(|line| strip_comment(line).trim())
+        .filter(|line| !line.is_empty())
+}
+
+/// The libraries known to Agda.
+#[derive(Debug, Clone, Default)]
+pub struct Libraries {
+    /// From the `libraries` file.
+    pub libraries: Vec<AgdaLib>,
+    /// From the `defaults` file, used by files outside of any library.
+    pub defaults: Vec<String>,
+    /// The entries of the `libraries` file that cannot be read.
+    pub errors: Vec<String>,
+}
+
+/// The include paths of a file, see [`Libraries::resolve`].
+#[derive(Debug, Clone, Default)]
+pub struct Resolution {
+    /// The library containing the file.
+    pub library: Option<AgdaLib>,
+    /// Of the library and its dependencies.
+    pub include_paths: Vec<PathBuf>,
+    /// Dependencies that are not in the `libraries` file.
+    pub missing: Vec<String>,
+}
+
+impl Libraries {
+    /// `AGDA_DIR`, or `~/.agda`.
+    pub fn agda_dir() -> Option<PathBuf> {
+        if let Some(dir) = env::var_os("AGDA_DIR") {
+            return Some(dir.into());
+        }
+        let home = env::var_os("HOME").or_else(|| env::var_os("USERPROFILE"))?;
+        Some(PathBuf::from(home).join(".agda"))
+    }
+
+    /// Read the `libraries` and `defaults` files in `agda_dir`, which may not exist.
+    pub fn read(agda_dir: &Path) -> io::Result<Self> {
+        let mut libraries = Libraries::default();
+        let read = |name: &str| {
+            let path = agda_dir.join(name);
+            if path.is_file() {
+                read_to_string(path)
+            } else {
+                Ok(String::new())
+            }
+        };
+        for path in entries(&read("libraries")?) {
+            match AgdaLib::read(PathBuf::from(path)) {
+                Ok(Ok(lib)) => libraries.libraries.push(lib),
+                Ok(Err(e)) => libraries.errors.push(format!("{}: {}", path, e)),
+                Err(e) => libraries.errors.push(format!("{}: {}", path, e)),
+            }
+        }
+        libraries.defaults = entries(&read("defaults")?).map(str::to_owned).collect();
+        Ok(libraries)
+    }
+
+    /// The library `name`, preferring the exact name to a versioned one.
+    pub fn find(&self, name: &str) -> Option<&AgdaLib> {
+        let exact = self
+            .libraries
+            .iter()
+            .find(|lib| lib.name.as_deref() == Some(name));
+        exact.or_else(|| self.libraries.iter().find(|lib| lib.is_named(name)))
+    }
+
+    /// The include paths of the libraries `names` and their dependencies.
+    pub fn resolve_names(&self, names: &[String]) -> Resolution {
+        let mut resolution = Resolution::default();
+        let mut seen = BTreeSet::new();
+        let mut todo: Vec<&str> = names.iter().map(|s| s.as_str()).rev().collect();
+        while let Some(name) = todo.pop() {
+            if !seen.insert(name) {
+                continue;
+            }
+            match self.find(name) {
+                Some(lib) => {
+                    resolution.include_paths.extend(lib.include_paths());
+                    todo.extend(lib.depend.iter().map(|s| s.as_str()).rev());
+                }
+                None => resolution.missing.push(name.to_owned()),
+            }
+        }
+        resolution
+    }
+
+    /// The library of `file` and the include paths, like Agda does:
+    /// the dependencies of the nearest `.agda-lib`, or the defaults.
+    pub fn resolve(&self, file: &Path) -> io::Result<Result<Resolution, String>> {
+        let library = match AgdaLib::find(file)? {
+            Some(path) => match AgdaLib::read(path)? {
+                Ok(lib) => Some(lib),
+                Err(e) => return Ok(Err(e)),
+            },
+            None => None,
+        };
+        let mut resolution = match &library {
+            Some(lib) => {
+                let mut resolution = self.resolve_names(&lib.depend);
+                let mut include_paths = lib.include_paths();
+                include_paths.append(&mut resolution.include_paths);
+                resolution.include_paths = include_paths;
+                resolution
+            }
+            None => self.resolve_names(&self.defaults),
+        };
+        resolution.library = library;
+        Ok(Ok(resolution))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn parse() {
+        let text = "name: my-lib -- a comment\n\
+                    include: src\n  test\n\
+                    depend:\n  standard-library, cubical\n\
+                    flags: --without-K\n";
+        let lib = AgdaLib::parse(PathBuf::from("/p/my.agda-lib"), text).unwrap();
+        assert_eq!(lib.name.as_deref(), Some("my-lib"));
+        assert_eq!(
+            lib.include,
+            vec![PathBuf::from("src"), PathBuf::from("test")]
+        );
+        assert_eq!(lib.depend, vec!["standard-library", "cubical"]);
+        assert_eq!(lib.flags, vec!["--without-K"]);
+        assert_eq!(lib.include_paths()[1], PathBuf::from("/p/test"));
+        assert!(AgdaLib::parse(PathBuf::new(), "  src").is_err());
+    }
+
+    #[test]
+    fn names() {
+        let named = |name: &str| AgdaLib {
+            name: Some(name.to_owned()),
+            ..Default::default()
+        };
+        assert!(named("standard-library-2.0").is_named("standard-library"));
+        assert!(!named("standard-library-ext").is_named("standard-library"));
+        let libraries = Libraries {
+            libraries: vec![named("a-1.0"), named("a")],
+            ..Default::default()
+        };
+        assert_eq!(libraries.find("a").unwrap().name.as_deref(), Some("a"));
+        let missing = libraries.resolve_names(&["b".to_owned()]).missing;
+        assert_eq!(missing, vec!["b"]);
+    }
+
+    #[test]
+    fn modules() {
+        let include = vec![PathBuf::from("/p/src")];
+        let name = module_name(&include, Path::new("/p/src/Data/Nat.lagda.md"));
+        assert_eq!(name.as_deref(), Some("Data.Nat"));
+        assert_eq!(module_name(&include, Path::new("/q/A.agda")), None);
+        let include = vec![PathBuf::from("/p"), PathBuf::from("/p/src")];
+        let name = module_name(&include, Path::new("/p/src/Data/Nat.agda"));
+        assert_eq!(name.as_deref(), Some("Data.Nat"));
+        assert!(check_module_name("Data.Nat-Properties′").is_ok());
+        assert!(check_module_name("Data..Nat").is_err());
+        assert!(check_module_name("my module").is_err());
+        assert!(check_module_name("Data.where").is_err());
+        assert!(check_module_name("42").is_err());
+    }
+}
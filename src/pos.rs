@@ -63,15 +63,16 @@ impl Display for InteractionPoint {
 
 /// IDK why is this needed, but Emacs passes it to Agda.
 /// It's fine to omit this in the commands.
-#[derive(Debug, Clone, Eq, PartialEq)]
+#[derive(Debug, Default, Clone, Eq, PartialEq)]
 pub enum AgdaRange {
+    #[default]
     NoRange,
     Range(Interval),
 }
 
-impl Into<Option<Interval>> for AgdaRange {
-    fn into(self) -> Option<Interval> {
-        match self {
+impl From<AgdaRange> for Option<Interval> {
+    fn from(r: AgdaRange) -> Self {
+        match r {
             AgdaRange::NoRange => None,
             AgdaRange::Range(i) => Some(i),
         }
@@ -84,12 +85,6 @@ impl From<Option<Interval>> for AgdaRange {
     }
 }
 
-impl Default for AgdaRange {
-    fn default() -> Self {
-        AgdaRange::NoRange
-    }
-}
-
 #[derive(Deserialize, Clone, Debug, Eq, PartialEq)]
 pub struct NamedMeta {
     pub name: String,
@@ -3,6 +3,7 @@ use crate::base::{Cohesion, ComputeMode, Hiding, Relevance};
 use crate::pos::InteractionPoint;
 use crate::resp::OutputForm;
 use serde::Deserialize;
+use std::fmt::{Display, Error, Formatter};
 
 #[derive(Deserialize, Clone, Default, Debug, Eq, PartialEq)]
 #[serde(rename_all = "camelCase")]
@@ -40,15 +41,15 @@ pub struct AgdaError {
     pub message: Option<String>,
 }
 
-impl Into<String> for AgdaError {
-    fn into(self) -> String {
-        self.message.unwrap_or_else(|| "Unknown error".to_owned())
+impl From<AgdaError> for String {
+    fn from(e: AgdaError) -> Self {
+        e.message.unwrap_or_else(|| "Unknown error".to_owned())
     }
 }
 
-impl<Ok> Into<Result<Ok, String>> for AgdaError {
-    fn into(self) -> Result<Ok, String> {
-        Err(self.into())
+impl<Ok> From<AgdaError> for Result<Ok, String> {
+    fn from(e: AgdaError) -> Self {
+        Err(e.into())
     }
 }
 
@@ -71,6 +72,20 @@ pub struct TelescopicItem {
     pub hiding: Hiding,
 }
 
+impl Display for TelescopicItem {
+    fn fmt(&self, f: &mut Formatter) -> Result<(), Error> {
+        let (open, close) = match self.hiding {
+            Hiding::NotHidden => ("(", ")"),
+            Hiding::Hidden => ("{", "}"),
+            Hiding::YesOverlap | Hiding::NoOverlap => ("⦃ ", " ⦄"),
+        };
+        match &self.name {
+            Some(name) => write!(f, "{}{} : {}{}", open, name, self.dom, close),
+            None => write!(f, "{}{}{}", open, self.dom, close),
+        }
+    }
+}
+
 #[derive(Deserialize, Clone, Debug, Eq, PartialEq)]
 #[serde(rename_all = "camelCase")]
 pub struct ModuleContents {
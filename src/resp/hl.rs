@@ -1,3 +1,6 @@
+use std::fs::read_to_string;
+use std::io;
+
 use crate::base::TokenBased;
 use crate::pos::IntPos;
 use either::Either;
@@ -49,4 +52,13 @@ impl HighlightingInfo {
             Either::Right(self.filepath.unwrap())
         }
     }
+
+    /// The highlighting, read from the file for indirect highlighting.
+    pub fn read(&self) -> io::Result<Highlighting> {
+        match (&self.info, &self.filepath) {
+            (Some(info), _) => Ok(info.clone()),
+            (None, Some(path)) => Ok(serde_json::from_str(&read_to_string(path)?)?),
+            (None, None) => Ok(Highlighting::default()),
+        }
+    }
 }
@@ -8,6 +8,7 @@ pub use self::give::*;
 pub use self::goal::*;
 pub use self::hl::*;
 pub use self::oc::*;
+pub use self::progress::*;
 
 /// Display info.
 mod di;
@@ -19,6 +20,8 @@ mod goal;
 mod hl;
 /// Output constraints (user goals & unsolved metas).
 mod oc;
+/// Module loading progress.
+mod progress;
 
 #[derive(Deserialize, Clone, Debug, Eq, PartialEq)]
 #[serde(rename_all = "camelCase")]
@@ -0,0 +1,125 @@
+use super::Resp;
+
+/// Module loading progress, parsed from [`Resp::RunningInfo`](super::Resp::RunningInfo).
+///
+/// Agda indents the messages by the depth of the import chain,
+/// which is kept as `depth`.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum Progress {
+    /// `Checking M (/path/M.agda).`
+    Checking {
+        module: String,
+        path: Option<String>,
+        depth: usize,
+    },
+    /// `Loading  M (/path/M.agdai).`, the module is loaded from its interface file.
+    Loading {
+        module: String,
+        path: Option<String>,
+        depth: usize,
+    },
+    /// `Skipping M (/path/M.agdai).`
+    Skipping {
+        module: String,
+        path: Option<String>,
+        depth: usize,
+    },
+    /// `Finished M.`
+    Finished { module: String, depth: usize },
+    /// Some other running information.
+    Message(String),
+    /// [`Resp::ClearRunningInfo`](super::Resp::ClearRunningInfo).
+    Clear,
+}
+
+impl Progress {
+    pub fn parse(message: &str) -> Self {
+        let trimmed = message.trim_start_matches(' ');
+        let depth = message.len() - trimmed.len();
+        let trimmed = trimmed.trim_end();
+        let mut words = trimmed.splitn(2, ' ');
+        let kind = words.next().unwrap_or_default();
+        let rest = words.next().unwrap_or_default().trim_start();
+        let (module, path) = match rest.find(" (") {
+            Some(idx) if rest.ends_with(").") => {
+                (&rest[..idx], Some(rest[idx + 2..rest.len() - 2].to_owned()))
+            }
+            _ => (rest.trim_end_matches('.'), None),
+        };
+        let module = module.to_owned();
+        let invalid = module.is_empty() || module.contains(char::is_whitespace);
+        match kind {
+            _ if invalid => Progress::Message(message.to_owned()),
+            "Checking" => Progress::Checking {
+                module,
+                path,
+                depth,
+            },
+            "Loading" => Progress::Loading {
+                module,
+                path,
+                depth,
+            },
+            "Skipping" => Progress::Skipping {
+                module,
+                path,
+                depth,
+            },
+            "Finished" if path.is_none() => Progress::Finished { module, depth },
+            _ => Progress::Message(message.to_owned()),
+        }
+    }
+
+    /// The module this progress is about.
+    pub fn module(&self) -> Option<&str> {
+        use Progress::*;
+        match self {
+            Checking { module, .. }
+            | Loading { module, .. }
+            | Skipping { module, .. }
+            | Finished { module, .. } => Some(module),
+            Message(_) | Clear => None,
+        }
+    }
+}
+
+impl Resp {
+    /// The debug level and the progress, for the responses about progress.
+    pub fn progress(&self) -> Option<(i32, Progress)> {
+        match self {
+            Resp::RunningInfo {
+                debug_level,
+                message,
+            } => Some((*debug_level, Progress::parse(message))),
+            Resp::ClearRunningInfo => Some((0, Progress::Clear)),
+            _ => None,
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn parse_progress() {
+        let checking = Progress::parse(" Checking Data.Nat (/lib/Data/Nat.agda).\n");
+        let expected = Progress::Checking {
+            module: "Data.Nat".to_owned(),
+            path: Some("/lib/Data/Nat.agda".to_owned()),
+            depth: 1,
+        };
+        assert_eq!(checking, expected);
+        let loading = Progress::parse("Loading  Agda.Primitive (/lib/Primitive.agdai).");
+        assert!(matches!(loading, Progress::Loading { depth: 0, .. }));
+        assert_eq!(loading.module(), Some("Agda.Primitive"));
+        let finished = Progress::parse("  Finished Data.Nat.");
+        let expected = Progress::Finished {
+            module: "Data.Nat".to_owned(),
+            depth: 2,
+        };
+        assert_eq!(finished, expected);
+        let other = Progress::parse("Compiling the file");
+        assert_eq!(other, Progress::Message("Compiling the file".to_owned()));
+    }
+}